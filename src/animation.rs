@@ -0,0 +1,672 @@
+use std::{
+    collections::HashMap,
+    ops::{Add, Mul},
+};
+
+use bevy::prelude::*;
+use gltf::animation::{util::ReadOutputs, Interpolation};
+
+use crate::{
+    document::{DocumentLoading, LoadedDocument},
+    explosion::ExplodedPart,
+    file_picker::FileChangedEvent,
+    hierarchy::NodeIndex,
+    ui::UIPanel,
+};
+
+/// This plugin lists the animations of the opened glTF file in a panel at the
+/// bottom of the screen and plays them by sampling their translation, rotation
+/// and scale channels into the poses of the drawn nodes
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Animations>()
+            .init_resource::<AnimationPlayer>()
+            .add_startup_system_to_stage(StartupStage::PostStartup, spawn_animation_panel)
+            .add_system(load_animations.after(DocumentLoading))
+            .add_system(animation_keys.label(AnimationLabel::Controls))
+            .add_system(animation_buttons.label(AnimationLabel::Controls))
+            .add_system(scrub_timeline.label(AnimationLabel::Controls))
+            .add_system(
+                advance_animation
+                    .label(AnimationLabel::Advance)
+                    .after(AnimationLabel::Controls),
+            )
+            .add_system(apply_animation.after(AnimationLabel::Advance))
+            .add_system(update_animation_panel);
+    }
+}
+
+#[derive(Clone, Hash, Debug, PartialEq, Eq, SystemLabel)]
+enum AnimationLabel {
+    Controls,
+    Advance,
+}
+
+/// The keyframes of a channel, read from the buffers of the file
+pub enum Keyframes {
+    Translation(Vec<Vec3>),
+    Rotation(Vec<Quat>),
+    Scale(Vec<Vec3>),
+    /// Morph target weights are listed, but not applied to the nodes
+    Weights,
+}
+
+pub struct Channel {
+    pub node: NodeIndex,
+    pub interpolation: Interpolation,
+    pub times: Vec<f32>,
+    pub keyframes: Keyframes,
+}
+
+impl Channel {
+    pub fn property(&self) -> &'static str {
+        match self.keyframes {
+            Keyframes::Translation(_) => "translation",
+            Keyframes::Rotation(_) => "rotation",
+            Keyframes::Scale(_) => "scale",
+            Keyframes::Weights => "weights",
+        }
+    }
+
+    /// Samples the channel at `time` into the pose of its node
+    fn apply(&self, time: f32, pose: &mut Transform) {
+        match &self.keyframes {
+            Keyframes::Translation(values) => {
+                pose.translation = sample(&self.times, values, self.interpolation, time, Vec3::lerp)
+            }
+            Keyframes::Rotation(values) => {
+                pose.rotation =
+                    sample(&self.times, values, self.interpolation, time, Quat::slerp).normalize()
+            }
+            Keyframes::Scale(values) => {
+                pose.scale = sample(&self.times, values, self.interpolation, time, Vec3::lerp)
+            }
+            Keyframes::Weights => {}
+        }
+    }
+}
+
+pub struct AnimationClip {
+    pub name: String,
+    pub duration: f32,
+    pub channels: Vec<Channel>,
+}
+
+/// The animations of the current file
+#[derive(Default)]
+struct Animations {
+    clips: Vec<AnimationClip>,
+}
+
+/// The state of the animation that is shown
+struct AnimationPlayer {
+    /// The index of the selected clip
+    current: Option<usize>,
+    time: f32,
+    playing: bool,
+    looping: bool,
+}
+
+impl Default for AnimationPlayer {
+    fn default() -> Self {
+        Self {
+            current: None,
+            time: 0.0,
+            playing: false,
+            looping: true,
+        }
+    }
+}
+
+fn load_animations(
+    mut reader: EventReader<FileChangedEvent>,
+    loaded: Res<LoadedDocument>,
+    mut animations: ResMut<Animations>,
+    mut player: ResMut<AnimationPlayer>,
+) {
+    if reader.iter().next().is_none() {
+        return;
+    }
+    *player = AnimationPlayer::default();
+    animations.clips = match &loaded.document {
+        Some(document) => document
+            .animations()
+            .map(|animation| read_animation(animation, &loaded))
+            .collect(),
+        None => vec![],
+    };
+    if !animations.clips.is_empty() {
+        player.current = Some(0);
+    }
+}
+
+pub fn read_animation(animation: gltf::Animation, loaded: &LoadedDocument) -> AnimationClip {
+    let channels: Vec<Channel> = animation
+        .channels()
+        .filter_map(|channel| {
+            let sampler = channel.sampler();
+            if !loaded.accessor_in_bounds(&sampler.input())
+                || !loaded.accessor_in_bounds(&sampler.output())
+            {
+                return None;
+            }
+            let reader =
+                channel.reader(|buffer| loaded.buffers.get(buffer.index()).map(|data| &data[..]));
+            let times: Vec<f32> = reader.read_inputs()?.collect();
+            let keyframes = match reader.read_outputs()? {
+                ReadOutputs::Translations(values) => {
+                    Keyframes::Translation(values.map(Vec3::from).collect())
+                }
+                ReadOutputs::Rotations(values) => {
+                    Keyframes::Rotation(values.into_f32().map(Quat::from_array).collect())
+                }
+                ReadOutputs::Scales(values) => Keyframes::Scale(values.map(Vec3::from).collect()),
+                ReadOutputs::MorphTargetWeights(_) => Keyframes::Weights,
+            };
+            // Cubic splines store an in-tangent, the value and an out-tangent per keyframe
+            let values_per_keyframe = match sampler.interpolation() {
+                Interpolation::CubicSpline => 3,
+                _ => 1,
+            };
+            let values = match &keyframes {
+                Keyframes::Translation(values) | Keyframes::Scale(values) => Some(values.len()),
+                Keyframes::Rotation(values) => Some(values.len()),
+                Keyframes::Weights => None,
+            };
+            if times.is_empty()
+                || values.is_some_and(|values| values != times.len() * values_per_keyframe)
+            {
+                warn!(
+                    "Skipping a channel of {}, output accessor {} does not match its {} keyframes",
+                    animation.name().unwrap_or("an animation"),
+                    sampler.output().index(),
+                    times.len()
+                );
+                return None;
+            }
+            Some(Channel {
+                node: NodeIndex(channel.target().node().index()),
+                interpolation: sampler.interpolation(),
+                times,
+                keyframes,
+            })
+        })
+        .collect();
+    let duration = channels
+        .iter()
+        .filter_map(|channel| channel.times.last())
+        .fold(0.0, |duration: f32, time| duration.max(*time));
+
+    AnimationClip {
+        name: animation
+            .name()
+            .map(|name| name.to_string())
+            .unwrap_or_else(|| format!("Animation {}", animation.index())),
+        duration,
+        channels,
+    }
+}
+
+/// Samples keyframes at `time`, holding the first and last value outside of their range
+fn sample<T>(
+    times: &[f32],
+    values: &[T],
+    interpolation: Interpolation,
+    time: f32,
+    lerp: fn(T, T, f32) -> T,
+) -> T
+where
+    T: Copy + Add<Output = T> + Mul<f32, Output = T>,
+{
+    // Cubic splines store an in-tangent, the value and an out-tangent per keyframe
+    let value = |index: usize| match interpolation {
+        Interpolation::CubicSpline => values[index * 3 + 1],
+        _ => values[index],
+    };
+    let next = times.partition_point(|keyframe_time| *keyframe_time <= time);
+    if next == 0 {
+        return value(0);
+    }
+    if next == times.len() {
+        return value(times.len() - 1);
+    }
+    let previous = next - 1;
+    let delta = times[next] - times[previous];
+    let t = (time - times[previous]) / delta;
+    match interpolation {
+        Interpolation::Step => value(previous),
+        Interpolation::Linear => lerp(value(previous), value(next), t),
+        Interpolation::CubicSpline => {
+            let out_tangent = values[previous * 3 + 2] * delta;
+            let in_tangent = values[next * 3] * delta;
+            let (t2, t3) = (t * t, t * t * t);
+            value(previous) * (2.0 * t3 - 3.0 * t2 + 1.0)
+                + out_tangent * (t3 - 2.0 * t2 + t)
+                + value(next) * (-2.0 * t3 + 3.0 * t2)
+                + in_tangent * (t3 - t2)
+        }
+    }
+}
+
+/// Space plays or pauses, L toggles looping
+fn animation_keys(keyboard_input: Res<Input<KeyCode>>, mut player: ResMut<AnimationPlayer>) {
+    if !keyboard_input.is_changed() || player.current.is_none() {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Space) {
+        player.playing = !player.playing;
+    } else if keyboard_input.just_pressed(KeyCode::L) {
+        player.looping = !player.looping;
+    }
+}
+
+fn advance_animation(
+    time: Res<Time>,
+    animations: Res<Animations>,
+    mut player: ResMut<AnimationPlayer>,
+) {
+    if !player.playing {
+        return;
+    }
+    let duration = match player.current.and_then(|index| animations.clips.get(index)) {
+        Some(clip) => clip.duration,
+        _ => return,
+    };
+    player.time += time.delta_seconds();
+    if player.time > duration {
+        if player.looping && duration > 0.0 {
+            player.time %= duration;
+        } else {
+            player.time = duration;
+            player.playing = false;
+        }
+    }
+}
+
+/// Sets the poses of the animated nodes, and resets all others to their
+/// original transform, whenever the time or the animation has changed
+fn apply_animation(
+    animations: Res<Animations>,
+    player: Res<AnimationPlayer>,
+    mut part_query: Query<(&NodeIndex, &mut ExplodedPart)>,
+) {
+    if !player.is_changed() {
+        return;
+    }
+    let clip = player.current.and_then(|index| animations.clips.get(index));
+    let mut channels: HashMap<NodeIndex, Vec<&Channel>> = HashMap::new();
+    for channel in clip.iter().flat_map(|clip| clip.channels.iter()) {
+        channels.entry(channel.node).or_default().push(channel);
+    }
+    for (index, mut part) in part_query.iter_mut() {
+        let mut pose = part.original;
+        for channel in channels.get(index).into_iter().flatten() {
+            channel.apply(player.time, &mut pose);
+        }
+        if part.pose != pose {
+            part.pose = pose;
+        }
+    }
+}
+
+#[derive(Component)]
+struct AnimationPanel;
+
+#[derive(Component)]
+struct AnimationText;
+
+/// The bar showing the progress of the animation, clicking or dragging on it
+/// moves to the corresponding time
+#[derive(Component)]
+struct Timeline;
+
+#[derive(Component)]
+struct TimelineMarker;
+
+#[derive(Component, Clone, Copy)]
+enum AnimationButton {
+    PlayPause,
+    Loop,
+    Previous,
+    Next,
+}
+
+fn spawn_animation_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    ui_panel_query: Query<Entity, With<UIPanel>>,
+) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 18.0,
+        color: Color::WHITE,
+    };
+    let panel = commands
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::ColumnReverse,
+                align_self: AlignSelf::FlexStart,
+                size: Size::new(Val::Px(420.0), Val::Auto),
+                padding: Rect::all(Val::Px(10.0)),
+                display: Display::None,
+                ..Default::default()
+            },
+            color: Color::rgb(0.10, 0.10, 0.10).into(),
+            ..Default::default()
+        })
+        .insert(AnimationPanel)
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section("", text_style.clone(), Default::default()),
+                    style: Style {
+                        max_size: Size::new(Val::Px(400.0), Val::Undefined),
+                        ..Default::default()
+                    },
+                    ..Default::default()
+                })
+                .insert(AnimationText);
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        margin: Rect {
+                            top: Val::Px(6.0),
+                            bottom: Val::Px(6.0),
+                            ..Default::default()
+                        },
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for (label, button) in [
+                        ("[<] ", AnimationButton::Previous),
+                        ("[Play/Pause] ", AnimationButton::PlayPause),
+                        ("[Loop] ", AnimationButton::Loop),
+                        ("[>]", AnimationButton::Next),
+                    ] {
+                        parent
+                            .spawn_bundle(TextBundle {
+                                text: Text::with_section(
+                                    label,
+                                    text_style.clone(),
+                                    Default::default(),
+                                ),
+                                ..Default::default()
+                            })
+                            .insert(Interaction::default())
+                            .insert(button);
+                    }
+                });
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        size: Size::new(Val::Percent(100.0), Val::Px(12.0)),
+                        ..Default::default()
+                    },
+                    color: Color::rgb(0.3, 0.3, 0.3).into(),
+                    ..Default::default()
+                })
+                .insert(Interaction::default())
+                .insert(Timeline)
+                .with_children(|parent| {
+                    parent
+                        .spawn_bundle(NodeBundle {
+                            style: Style {
+                                size: Size::new(Val::Percent(0.0), Val::Percent(100.0)),
+                                ..Default::default()
+                            },
+                            color: Color::GREEN.into(),
+                            ..Default::default()
+                        })
+                        .insert(TimelineMarker);
+                });
+        })
+        .id();
+    // Between the left and the right panel
+    commands
+        .entity(ui_panel_query.single())
+        .insert_children(1, &[panel]);
+}
+
+fn animation_buttons(
+    interaction_query: Query<(&Interaction, &AnimationButton), Changed<Interaction>>,
+    animations: Res<Animations>,
+    mut player: ResMut<AnimationPlayer>,
+) {
+    let count = animations.clips.len();
+    for (_, button) in interaction_query
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Clicked)
+    {
+        let current = match player.current {
+            Some(it) => it,
+            _ => return,
+        };
+        match button {
+            AnimationButton::PlayPause => player.playing = !player.playing,
+            AnimationButton::Loop => player.looping = !player.looping,
+            AnimationButton::Previous => {
+                player.current = Some((current + count - 1) % count);
+                player.time = 0.0;
+            }
+            AnimationButton::Next => {
+                player.current = Some((current + 1) % count);
+                player.time = 0.0;
+            }
+        }
+    }
+}
+
+/// Moves to the time under the cursor while the timeline is pressed
+fn scrub_timeline(
+    windows: Res<Windows>,
+    animations: Res<Animations>,
+    mut player: ResMut<AnimationPlayer>,
+    timeline_query: Query<(&Interaction, &Node, &GlobalTransform), With<Timeline>>,
+) {
+    let (interaction, node, transform) = match timeline_query.get_single() {
+        Ok(it) => it,
+        _ => return,
+    };
+    if *interaction != Interaction::Clicked {
+        return;
+    }
+    let cursor = match windows
+        .get_primary()
+        .and_then(|window| window.cursor_position())
+    {
+        Some(it) => it,
+        _ => return,
+    };
+    let duration = match player.current.and_then(|index| animations.clips.get(index)) {
+        Some(clip) => clip.duration,
+        _ => return,
+    };
+    // UI nodes are positioned by their center
+    let left = transform.translation.x - node.size.x / 2.0;
+    let progress = ((cursor.x - left) / node.size.x).clamp(0.0, 1.0);
+    player.time = progress * duration;
+    player.playing = false;
+}
+
+fn update_animation_panel(
+    animations: Res<Animations>,
+    player: Res<AnimationPlayer>,
+    mut panel_query: Query<&mut Style, (With<AnimationPanel>, Without<TimelineMarker>)>,
+    mut text_query: Query<&mut Text, With<AnimationText>>,
+    mut marker_query: Query<&mut Style, (With<TimelineMarker>, Without<AnimationPanel>)>,
+) {
+    if !player.is_changed() && !animations.is_changed() {
+        return;
+    }
+    let clip = player.current.and_then(|index| animations.clips.get(index));
+    panel_query.single_mut().display = match clip {
+        Some(_) => Display::Flex,
+        None => Display::None,
+    };
+    let clip = match clip {
+        Some(it) => it,
+        _ => return,
+    };
+
+    let mut lines = vec![format!(
+        "Animation {}/{}: {}",
+        player.current.unwrap() + 1,
+        animations.clips.len(),
+        clip.name
+    )];
+    lines.push(format!(
+        "{:.2} / {:.2} s {}{}",
+        player.time,
+        clip.duration,
+        if player.playing { "playing" } else { "paused" },
+        if player.looping { ", looping" } else { "" }
+    ));
+    lines.push(format!("{} channels:", clip.channels.len()));
+    const SHOWN_CHANNELS: usize = 8;
+    for channel in clip.channels.iter().take(SHOWN_CHANNELS) {
+        lines.push(format!(
+            "  Node {} {} ({:?}, {} keys)",
+            channel.node.0,
+            channel.property(),
+            channel.interpolation,
+            channel.times.len()
+        ));
+    }
+    if clip.channels.len() > SHOWN_CHANNELS {
+        lines.push(format!(
+            "  ... {} more",
+            clip.channels.len() - SHOWN_CHANNELS
+        ));
+    }
+    text_query.single_mut().sections[0].value = lines.join("\n");
+
+    let progress = if clip.duration > 0.0 {
+        player.time / clip.duration
+    } else {
+        0.0
+    };
+    marker_query.single_mut().size.width = Val::Percent(progress * 100.0);
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    /// An animation of node 0 whose sampler reads `times` as input and `values`
+    /// as output, both stored in one buffer
+    fn animated_document(times: &[f32], values: &[f32], interpolation: &str) -> LoadedDocument {
+        let bytes: Vec<u8> = times
+            .iter()
+            .chain(values)
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let times_length = times.len() * 4;
+        LoadedDocument::from_json(&json!({
+            "asset": { "version": "2.0" },
+            "nodes": [{}],
+            "buffers": [{
+                "byteLength": bytes.len(),
+                "uri": format!("data:application/octet-stream;base64,{}", base64::encode(&bytes)),
+            }],
+            "bufferViews": [
+                { "buffer": 0, "byteLength": times_length },
+                { "buffer": 0, "byteOffset": times_length, "byteLength": values.len() * 4 },
+            ],
+            "accessors": [
+                {
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": times.len(),
+                    "type": "SCALAR",
+                    "min": [times.first()],
+                    "max": [times.last()],
+                },
+                {
+                    "bufferView": 1,
+                    "componentType": 5126,
+                    "count": values.len() / 3,
+                    "type": "VEC3",
+                },
+            ],
+            "animations": [{
+                "channels": [{ "sampler": 0, "target": { "node": 0, "path": "translation" } }],
+                "samplers": [{ "input": 0, "output": 1, "interpolation": interpolation }],
+            }],
+        }))
+    }
+
+    fn read_first_animation(loaded: &LoadedDocument) -> AnimationClip {
+        let animation = loaded
+            .document
+            .as_ref()
+            .unwrap()
+            .animations()
+            .next()
+            .unwrap();
+        read_animation(animation, loaded)
+    }
+
+    #[test]
+    fn samples_step_and_linear_keyframes() {
+        let times = [0.0, 1.0, 3.0];
+        let values = [0.0, 10.0, 30.0];
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let linear = |time| sample(&times, &values, Interpolation::Linear, time, lerp);
+        assert_eq!(linear(-1.0), 0.0);
+        assert_eq!(linear(0.5), 5.0);
+        assert_eq!(linear(2.0), 20.0);
+        assert_eq!(linear(5.0), 30.0);
+        assert_eq!(
+            sample(&times, &values, Interpolation::Step, 2.0, lerp),
+            10.0
+        );
+    }
+
+    #[test]
+    fn samples_cubic_splines_through_their_values() {
+        let times = [0.0, 1.0];
+        // In-tangent, value and out-tangent per keyframe
+        let values = [0.0, 1.0, 0.0, 0.0, 3.0, 0.0];
+        let lerp = |a: f32, b: f32, t: f32| a + (b - a) * t;
+        let cubic = |time| sample(&times, &values, Interpolation::CubicSpline, time, lerp);
+        assert_eq!(cubic(0.0), 1.0);
+        assert_eq!(cubic(1.0), 3.0);
+        assert_eq!(cubic(0.5), 2.0);
+    }
+
+    #[test]
+    fn reads_matching_channels() {
+        let loaded = animated_document(&[0.0, 2.0], &[0.0, 0.0, 0.0, 1.0, 2.0, 3.0], "LINEAR");
+        let clip = read_first_animation(&loaded);
+        assert_eq!(clip.duration, 2.0);
+        assert_eq!(clip.channels.len(), 1);
+        let mut pose = Transform::default();
+        clip.channels[0].apply(1.0, &mut pose);
+        assert_eq!(pose.translation, Vec3::new(0.5, 1.0, 1.5));
+    }
+
+    #[test]
+    fn drops_channels_with_too_few_outputs() {
+        let loaded = animated_document(&[0.0, 1.0, 2.0], &[0.0, 0.0, 0.0, 1.0, 1.0, 1.0], "LINEAR");
+        assert!(read_first_animation(&loaded).channels.is_empty());
+        // Cubic splines need three values per keyframe
+        let loaded = animated_document(&[0.0, 1.0], &[0.0, 0.0, 0.0, 1.0, 1.0, 1.0], "CUBICSPLINE");
+        assert!(read_first_animation(&loaded).channels.is_empty());
+    }
+
+    #[test]
+    fn drops_channels_reading_past_their_buffer_view() {
+        let mut loaded = animated_document(&[0.0, 1.0], &[0.0, 0.0, 0.0, 1.0, 1.0, 1.0], "LINEAR");
+        // The buffer is shorter than its views, as in a truncated file
+        loaded.buffers[0].0.truncate(12);
+        assert!(read_first_animation(&loaded).channels.is_empty());
+    }
+}
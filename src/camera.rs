@@ -1,19 +1,51 @@
 /// Taken from https://bevy-cheatbook.github.io/cookbook/pan-orbit-camera.html
 /// Last accessed: 2021-12-09
-use bevy::{input::mouse::*, prelude::*, render::camera::PerspectiveProjection};
+use bevy::{
+    input::mouse::*,
+    prelude::*,
+    render::{
+        camera::{ActiveCameras, OrthographicProjection, PerspectiveProjection, ScalingMode},
+        primitives::Aabb,
+    },
+};
+use std::f32::consts::{FRAC_PI_2, FRAC_PI_4, PI};
 
-use crate::{file_picker::FileChangedEvent, InspectorState};
+use crate::{
+    document::LoadedDocument,
+    hierarchy::{NodeIndex, PrimitiveIdentifier, ViewpointButton},
+    scene_picker::{SceneChangedEvent, SceneSelection},
+    selection::Selection,
+    utils::{get_current_scene, get_gltf, scene_bounds, Bounds},
+    InspectorState,
+};
 use bevy_mod_picking::PickingCameraBundle;
 
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(pan_orbit_camera)
-            .add_system(set_camera_on_scene_change);
+        app.add_event::<FocusEvent>()
+            .add_system(pan_orbit_camera)
+            .add_system(set_camera_on_scene_change.after(SceneSelection))
+            .add_system(frame_on_key)
+            .add_system(focus_camera)
+            .init_resource::<ProjectionMode>()
+            .add_system(view_presets)
+            .add_system(animate_view)
+            .add_system(toggle_projection)
+            .add_system(apply_projection)
+            .add_system(sync_orthographic_scale)
+            .init_resource::<Viewpoint>()
+            .add_event::<ViewpointEvent>()
+            .add_system(cycle_viewpoints)
+            .add_system(viewpoint_buttons)
+            .add_system(switch_viewpoint);
     }
 }
 
+/// Moves the camera so the given entities fill the view
+pub struct FocusEvent(pub Vec<Entity>);
+
 /// Tags an entity as capable of panning and orbiting.
 #[derive(Component)]
 pub struct PanOrbitCamera {
@@ -39,7 +71,11 @@ fn pan_orbit_camera(
     mut ev_motion: EventReader<MouseMotion>,
     mut ev_scroll: EventReader<MouseWheel>,
     input_mouse: Res<Input<MouseButton>>,
-    mut query: Query<(&mut PanOrbitCamera, &mut Transform, &PerspectiveProjection)>,
+    mut query: Query<(
+        &mut PanOrbitCamera,
+        &mut Transform,
+        Option<&PerspectiveProjection>,
+    )>,
     keyboard_input: Res<Input<KeyCode>>,
 ) {
     // change input mapping for orbit and panning here
@@ -100,7 +136,8 @@ fn pan_orbit_camera(
             any = true;
             // make panning distance independent of resolution and FOV,
             let window = get_primary_window_size(&windows);
-            pan *= Vec2::new(projection.fov * projection.aspect_ratio, projection.fov) / window;
+            let fov = field_of_view(projection);
+            pan *= Vec2::new(fov * window.x / window.y, fov) / window;
             // translate by local axes
             let right = transform.rotation * Vec3::X * -pan.x;
             let up = transform.rotation * Vec3::Y * pan.y;
@@ -127,12 +164,25 @@ fn pan_orbit_camera(
 
 fn get_primary_window_size(windows: &Res<Windows>) -> Vec2 {
     let window = windows.get_primary().unwrap();
-    Vec2::new(window.width() as f32, window.height() as f32)
+    Vec2::new(window.width(), window.height())
+}
+
+/// The orthographic projection shows what the default perspective projection
+/// would show at the focus point, so both use its field of view
+fn field_of_view(projection: Option<&PerspectiveProjection>) -> f32 {
+    projection
+        .map(|projection| projection.fov)
+        .unwrap_or_else(|| PerspectiveProjection::default().fov)
+}
+
+/// The distance from the center of the bounds at which they fill the field of view
+fn framing_radius(bounds: &Bounds, fov: f32) -> f32 {
+    f32::max(bounds.radius() / (fov / 2.0).sin(), 0.05)
 }
 
 /// Spawn a camera like this
-fn spawn_camera(commands: &mut Commands, transform: Transform) {
-    let radius = transform.translation.length();
+fn spawn_camera(commands: &mut Commands, transform: Transform, focus: Vec3) {
+    let radius = transform.translation.distance(focus);
 
     commands
         .spawn_bundle(PerspectiveCameraBundle {
@@ -140,16 +190,21 @@ fn spawn_camera(commands: &mut Commands, transform: Transform) {
             ..Default::default()
         })
         .insert(PanOrbitCamera {
+            focus,
             radius,
             ..Default::default()
         })
         .insert_bundle(PickingCameraBundle::default());
 }
 
+/// Spawns the camera for a newly opened file, looking from the direction of
+/// the file's camera transform at the center of the scene's bounds
 fn set_camera_on_scene_change(
-    mut reader: EventReader<FileChangedEvent>,
+    mut reader: EventReader<SceneChangedEvent>,
     mut commands: Commands,
     mut state: ResMut<InspectorState>,
+    loaded: Res<LoadedDocument>,
+    mut viewpoint: ResMut<Viewpoint>,
     query: Query<Entity, With<PanOrbitCamera>>,
 ) {
     if reader.iter().next().is_none() {
@@ -159,7 +214,407 @@ fn set_camera_on_scene_change(
         commands.entity(entity).despawn()
     }
     if let Some(file) = state.current_file {
-        spawn_camera(&mut commands, file.camera_transform);
+        let bounds =
+            get_gltf(&loaded).and_then(|gltf| scene_bounds(get_current_scene(&state, gltf)));
+        match bounds {
+            Some(bounds) => {
+                let mut transform = file.camera_transform;
+                let radius = framing_radius(&bounds, PerspectiveProjection::default().fov);
+                transform.translation =
+                    bounds.center() + transform.rotation * Vec3::new(0.0, 0.0, radius);
+                spawn_camera(&mut commands, transform, bounds.center());
+            }
+            None => spawn_camera(&mut commands, file.camera_transform, Vec3::ZERO),
+        }
     }
+    // The cameras of the file are despawned together with its scene
+    viewpoint.active = None;
     state.explosion_factor = 0.0;
+    state.explosion_target = 0.0;
+}
+
+/// Orbits the camera around the center of the focused entities, at a
+/// distance that fits their bounds into the field of view
+fn focus_camera(
+    mut reader: EventReader<FocusEvent>,
+    mut camera_query: Query<(
+        &mut PanOrbitCamera,
+        &mut Transform,
+        Option<&PerspectiveProjection>,
+    )>,
+    bounds_query: Query<(&Aabb, &GlobalTransform)>,
+) {
+    for event in reader.iter() {
+        let bounds = event
+            .0
+            .iter()
+            .filter_map(|entity| bounds_query.get(*entity).ok())
+            .map(|(aabb, transform)| {
+                Bounds {
+                    min: aabb.center - aabb.half_extents,
+                    max: aabb.center + aabb.half_extents,
+                }
+                .transformed(transform.compute_matrix())
+            })
+            .reduce(Bounds::union);
+        let bounds = match bounds {
+            Some(it) => it,
+            _ => continue,
+        };
+        for (mut pan_orbit, mut transform, projection) in camera_query.iter_mut() {
+            pan_orbit.focus = bounds.center();
+            pan_orbit.radius = framing_radius(&bounds, field_of_view(projection));
+            let rot_matrix = Mat3::from_quat(transform.rotation);
+            transform.translation =
+                pan_orbit.focus + rot_matrix.mul_vec3(Vec3::new(0.0, 0.0, pan_orbit.radius));
+        }
+    }
+}
+
+/// Frames the current selection, or the whole model if nothing is selected
+fn frame_on_key(
+    keyboard_input: Res<Input<KeyCode>>,
+    selection: Res<Selection>,
+    primitive_query: Query<Entity, With<PrimitiveIdentifier>>,
+    mut writer: EventWriter<FocusEvent>,
+) {
+    if !keyboard_input.is_changed() || !keyboard_input.just_pressed(KeyCode::F) {
+        return;
+    }
+    let entities = if selection.entities.is_empty() {
+        primitive_query.iter().collect()
+    } else {
+        selection.entities.clone()
+    };
+    writer.send(FocusEvent(entities));
+}
+
+/// Standard orientations the camera can be moved to around its focus point
+#[derive(Clone, Copy, Debug)]
+enum ViewPreset {
+    Front,
+    Back,
+    Left,
+    Right,
+    Top,
+    Bottom,
+    Iso,
+}
+
+impl ViewPreset {
+    /// The rotation of a camera looking at the focus from this side
+    fn rotation(self) -> Quat {
+        match self {
+            ViewPreset::Front => Quat::IDENTITY,
+            ViewPreset::Back => Quat::from_rotation_y(PI),
+            ViewPreset::Left => Quat::from_rotation_y(-FRAC_PI_2),
+            ViewPreset::Right => Quat::from_rotation_y(FRAC_PI_2),
+            ViewPreset::Top => Quat::from_rotation_x(-FRAC_PI_2),
+            ViewPreset::Bottom => Quat::from_rotation_x(FRAC_PI_2),
+            // Looking down the diagonal of a cube
+            ViewPreset::Iso => {
+                Quat::from_rotation_y(FRAC_PI_4)
+                    * Quat::from_rotation_x(-(1.0 / 2.0_f32.sqrt()).atan())
+            }
+        }
+    }
+}
+
+/// Rotates the camera around its focus towards a preset orientation
+#[derive(Component)]
+struct ViewTransition {
+    from: Quat,
+    to: Quat,
+    elapsed: f32,
+}
+
+/// How long moving to a view preset takes
+const VIEW_TRANSITION_SECONDS: f32 = 0.4;
+
+/// Numpad 1, 3 and 7 (or the number keys) show the front, right and top view,
+/// with Ctrl the opposite side. 9 shows the isometric view.
+fn view_presets(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut commands: Commands,
+    query: Query<(Entity, &Transform), With<PanOrbitCamera>>,
+) {
+    if !keyboard_input.is_changed() {
+        return;
+    }
+    let opposite =
+        keyboard_input.pressed(KeyCode::LControl) || keyboard_input.pressed(KeyCode::RControl);
+    let pressed = |keys: [KeyCode; 2]| keys.iter().any(|key| keyboard_input.just_pressed(*key));
+    let preset = if pressed([KeyCode::Numpad1, KeyCode::Key1]) {
+        if opposite {
+            ViewPreset::Back
+        } else {
+            ViewPreset::Front
+        }
+    } else if pressed([KeyCode::Numpad3, KeyCode::Key3]) {
+        if opposite {
+            ViewPreset::Left
+        } else {
+            ViewPreset::Right
+        }
+    } else if pressed([KeyCode::Numpad7, KeyCode::Key7]) {
+        if opposite {
+            ViewPreset::Bottom
+        } else {
+            ViewPreset::Top
+        }
+    } else if pressed([KeyCode::Numpad9, KeyCode::Key9]) {
+        ViewPreset::Iso
+    } else {
+        return;
+    };
+    for (entity, transform) in query.iter() {
+        commands.entity(entity).insert(ViewTransition {
+            from: transform.rotation,
+            to: preset.rotation(),
+            elapsed: 0.0,
+        });
+    }
+}
+
+fn animate_view(
+    time: Res<Time>,
+    mut commands: Commands,
+    mut query: Query<(
+        Entity,
+        &mut PanOrbitCamera,
+        &mut Transform,
+        &mut ViewTransition,
+    )>,
+) {
+    for (entity, mut pan_orbit, mut transform, mut transition) in query.iter_mut() {
+        transition.elapsed += time.delta_seconds();
+        let t = (transition.elapsed / VIEW_TRANSITION_SECONDS).min(1.0);
+        // smoothstep, so the camera starts and stops gently
+        let eased = t * t * (3.0 - 2.0 * t);
+        transform.rotation = transition.from.slerp(transition.to, eased);
+        transform.translation =
+            pan_orbit.focus + transform.rotation * Vec3::new(0.0, 0.0, pan_orbit.radius);
+        if t >= 1.0 {
+            pan_orbit.upside_down = false;
+            commands.entity(entity).remove::<ViewTransition>();
+        }
+    }
+}
+
+/// Whether the camera uses an orthographic instead of a perspective projection.
+/// Kept as a resource so it survives the camera being respawned for another file.
+#[derive(Default)]
+struct ProjectionMode {
+    orthographic: bool,
+}
+
+/// Numpad 5 or O toggles between perspective and orthographic projection
+fn toggle_projection(keyboard_input: Res<Input<KeyCode>>, mut mode: ResMut<ProjectionMode>) {
+    if keyboard_input.is_changed()
+        && (keyboard_input.just_pressed(KeyCode::Numpad5)
+            || keyboard_input.just_pressed(KeyCode::O))
+    {
+        mode.orthographic = !mode.orthographic;
+    }
+}
+
+/// Swaps the projection component of the camera to match the [ProjectionMode]
+fn apply_projection(
+    mode: Res<ProjectionMode>,
+    mut commands: Commands,
+    query: Query<(
+        Entity,
+        &PanOrbitCamera,
+        Option<&PerspectiveProjection>,
+        Option<&OrthographicProjection>,
+    )>,
+) {
+    for (entity, pan_orbit, perspective, orthographic) in query.iter() {
+        if mode.orthographic && perspective.is_some() {
+            commands
+                .entity(entity)
+                .remove::<PerspectiveProjection>()
+                .insert(OrthographicProjection {
+                    scaling_mode: ScalingMode::FixedVertical,
+                    scale: orthographic_scale(pan_orbit),
+                    // Parts between the camera and the focus stay visible when zooming in
+                    near: -ORTHOGRAPHIC_DEPTH,
+                    far: ORTHOGRAPHIC_DEPTH,
+                    ..Default::default()
+                });
+        } else if !mode.orthographic && orthographic.is_some() {
+            commands
+                .entity(entity)
+                .remove::<OrthographicProjection>()
+                .insert(PerspectiveProjection::default());
+        }
+    }
+}
+
+/// Depth range of the orthographic projection in front of and behind the camera
+const ORTHOGRAPHIC_DEPTH: f32 = 10000.0;
+
+/// Half the height of the view, so the focus point looks the same size as with
+/// the perspective projection
+fn orthographic_scale(pan_orbit: &PanOrbitCamera) -> f32 {
+    pan_orbit.radius * (field_of_view(None) / 2.0).tan()
+}
+
+/// Zooming changes the radius, which the orthographic projection has to follow
+fn sync_orthographic_scale(
+    mut query: Query<(&PanOrbitCamera, &mut OrthographicProjection), Changed<PanOrbitCamera>>,
+) {
+    for (pan_orbit, mut projection) in query.iter_mut() {
+        projection.scale = orthographic_scale(pan_orbit);
+    }
+}
+
+/// The camera node of the file the scene is viewed through,
+/// None if the orbit camera is used
+#[derive(Default)]
+struct Viewpoint {
+    active: Option<NodeIndex>,
+}
+
+/// Switches to the camera of the node, or back to the orbit camera
+struct ViewpointEvent(Option<NodeIndex>);
+
+/// Tags the camera spawned for a camera node of the file
+#[derive(Component)]
+struct GltfViewpoint;
+
+/// C cycles through the cameras of the scene and back to the orbit camera
+fn cycle_viewpoints(
+    keyboard_input: Res<Input<KeyCode>>,
+    state: Res<InspectorState>,
+    loaded: Res<LoadedDocument>,
+    viewpoint: Res<Viewpoint>,
+    mut writer: EventWriter<ViewpointEvent>,
+) {
+    if !keyboard_input.is_changed() || !keyboard_input.just_pressed(KeyCode::C) {
+        return;
+    }
+    let gltf = match get_gltf(&loaded) {
+        Some(it) => it,
+        _ => return,
+    };
+    let mut camera_nodes = vec![];
+    for node in get_current_scene(&state, gltf) {
+        collect_camera_nodes(node, &mut camera_nodes);
+    }
+    let next = match viewpoint.active {
+        Some(active) => camera_nodes
+            .iter()
+            .position(|node| *node == active)
+            .and_then(|index| camera_nodes.get(index + 1)),
+        None => camera_nodes.first(),
+    };
+    writer.send(ViewpointEvent(next.copied()));
+}
+
+fn collect_camera_nodes(node: gltf::Node, camera_nodes: &mut Vec<NodeIndex>) {
+    if node.camera().is_some() {
+        camera_nodes.push(NodeIndex(node.index()));
+    }
+    for child in node.children() {
+        collect_camera_nodes(child, camera_nodes);
+    }
+}
+
+fn viewpoint_buttons(
+    interaction_query: Query<(&Interaction, &ViewpointButton), Changed<Interaction>>,
+    mut writer: EventWriter<ViewpointEvent>,
+) {
+    for (_, button) in interaction_query
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Clicked)
+    {
+        writer.send(ViewpointEvent(Some(button.0)));
+    }
+}
+
+/// Spawns a camera with the projection of the file's camera as a child of its
+/// node, so it follows the node when the model is exploded, and makes it the
+/// active camera and the source of picking
+#[allow(clippy::too_many_arguments)]
+fn switch_viewpoint(
+    mut reader: EventReader<ViewpointEvent>,
+    mut commands: Commands,
+    mut viewpoint: ResMut<Viewpoint>,
+    mut active_cameras: ResMut<ActiveCameras>,
+    loaded: Res<LoadedDocument>,
+    viewpoint_query: Query<Entity, With<GltfViewpoint>>,
+    orbit_query: Query<Entity, With<PanOrbitCamera>>,
+    scene_node_query: Query<(Entity, &NodeIndex), Without<Node>>,
+) {
+    let event = match reader.iter().last() {
+        Some(it) => it,
+        _ => return,
+    };
+    for entity in viewpoint_query.iter() {
+        commands.entity(entity).despawn_recursive();
+    }
+    viewpoint.active = None;
+
+    let camera = event
+        .0
+        .zip(get_gltf(&loaded))
+        .and_then(|(node, gltf)| {
+            gltf.nodes()
+                .nth(node.0)?
+                .camera()
+                .map(|camera| (node, camera))
+        })
+        .and_then(|(node, camera)| {
+            let (node_entity, _) = scene_node_query.iter().find(|(_, index)| **index == node)?;
+            let camera_entity = spawn_gltf_camera(&mut commands, camera);
+            commands.entity(node_entity).add_child(camera_entity);
+            viewpoint.active = Some(node);
+            Some(camera_entity)
+        })
+        .or_else(|| orbit_query.iter().next());
+    if let Some(active) = active_cameras.get_mut(bevy::render::camera::CameraPlugin::CAMERA_3D) {
+        active.entity = camera;
+    }
+    // Clicks pick from the camera the scene is shown through
+    for entity in orbit_query.iter() {
+        commands
+            .entity(entity)
+            .remove_bundle::<PickingCameraBundle>();
+    }
+    if let Some(camera) = camera {
+        commands
+            .entity(camera)
+            .insert_bundle(PickingCameraBundle::default());
+    }
+}
+
+fn spawn_gltf_camera(commands: &mut Commands, camera: gltf::Camera) -> Entity {
+    match camera.projection() {
+        gltf::camera::Projection::Perspective(perspective) => commands
+            .spawn_bundle(PerspectiveCameraBundle {
+                perspective_projection: PerspectiveProjection {
+                    fov: perspective.yfov(),
+                    near: perspective.znear(),
+                    far: perspective.zfar().unwrap_or(1000.0),
+                    ..Default::default()
+                },
+                ..PerspectiveCameraBundle::new_3d()
+            })
+            .insert(GltfViewpoint)
+            .id(),
+        gltf::camera::Projection::Orthographic(orthographic) => commands
+            .spawn_bundle(OrthographicCameraBundle {
+                orthographic_projection: OrthographicProjection {
+                    scaling_mode: ScalingMode::FixedVertical,
+                    scale: orthographic.ymag(),
+                    near: orthographic.znear(),
+                    far: orthographic.zfar(),
+                    ..OrthographicCameraBundle::new_3d().orthographic_projection
+                },
+                ..OrthographicCameraBundle::new_3d()
+            })
+            .insert(GltfViewpoint)
+            .id(),
+    }
 }
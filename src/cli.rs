@@ -0,0 +1,148 @@
+/// This module implements the headless subcommands, which print information
+/// about a file without opening a window
+use std::path::Path;
+
+use crate::{
+    convert::{self, Format},
+    document::LoadedDocument,
+    report::Report,
+    validation::{self, hierarchy_issues, Severity},
+};
+
+const USAGE: &str = "Usage: gltf_inspector inspect <file> [--json]
+       gltf_inspector validate <file> [--json]
+       gltf_inspector convert <input> <output.glb|output.gltf> [--embed]";
+
+/// Runs the subcommand named by the first argument and returns its exit code,
+/// or None if the arguments are files to open in the inspector
+pub fn run(args: &[String]) -> Option<i32> {
+    match args.first().map(String::as_str) {
+        Some("inspect") => Some(inspect(&args[1..])),
+        Some("validate") => Some(validate(&args[1..])),
+        Some("convert") => Some(convert(&args[1..])),
+        _ => None,
+    }
+}
+
+/// Splits the arguments of a subcommand into the file and whether JSON is printed
+fn file_argument(args: &[String]) -> Option<(&String, bool)> {
+    let json = args.iter().any(|arg| arg == "--json");
+    let path = args.iter().find(|arg| !arg.starts_with("--"))?;
+    Some((path, json))
+}
+
+fn inspect(args: &[String]) -> i32 {
+    let (path, json) = match file_argument(args) {
+        Some(it) => it,
+        None => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    let loaded = match LoadedDocument::import(Path::new(path)) {
+        Ok(it) => it,
+        Err(err) => {
+            eprintln!("Could not load {}: {}", path, err);
+            return 1;
+        }
+    };
+    if let Some(issue) = loaded.document.iter().flat_map(hierarchy_issues).next() {
+        eprintln!("Could not inspect {}: {}", path, issue);
+        return 1;
+    }
+    let report = match Report::new(path, &loaded) {
+        Some(it) => it,
+        None => return 1,
+    };
+    if json {
+        match gltf::json::serialize::to_string_pretty(&report) {
+            Ok(json) => println!("{}", json),
+            Err(err) => {
+                eprintln!("Could not serialize the report: {}", err);
+                return 1;
+            }
+        }
+    } else {
+        println!("{}", report.to_text());
+    }
+    0
+}
+
+/// Prints the issues of a file and fails if any of them is an error
+fn validate(args: &[String]) -> i32 {
+    let (path, json) = match file_argument(args) {
+        Some(it) => it,
+        None => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    let issues = match LoadedDocument::import(Path::new(path)) {
+        Ok(loaded) => validation::validate(&loaded),
+        Err(err) => validation::load_error_issues(&err),
+    };
+    if json {
+        match gltf::json::serialize::to_string_pretty(&issues) {
+            Ok(json) => println!("{}", json),
+            Err(err) => {
+                eprintln!("Could not serialize the issues: {}", err);
+                return 1;
+            }
+        }
+    } else {
+        for issue in &issues {
+            println!("{}", issue);
+        }
+        println!(
+            "{}: {} errors, {} warnings, {} infos",
+            path,
+            validation::count(&issues, Severity::Error),
+            validation::count(&issues, Severity::Warning),
+            validation::count(&issues, Severity::Info)
+        );
+    }
+    if validation::count(&issues, Severity::Error) > 0 {
+        1
+    } else {
+        0
+    }
+}
+
+/// Writes a file in the container given by the extension of the output, a
+/// .gltf file embeds its buffers and images with `--embed`
+fn convert(args: &[String]) -> i32 {
+    let embed = args.iter().any(|arg| arg == "--embed");
+    let paths: Vec<&String> = args.iter().filter(|arg| !arg.starts_with("--")).collect();
+    let (input, output) = match paths[..] {
+        [input, output] => (Path::new(input), Path::new(output)),
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    let extension = output
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase);
+    let format = match (extension.as_deref(), embed) {
+        (Some("glb"), _) => Format::Glb,
+        (Some("gltf"), false) => Format::Separate,
+        (Some("gltf"), true) => Format::Embedded,
+        _ => {
+            eprintln!("{}", USAGE);
+            return 2;
+        }
+    };
+    match convert::convert(input, output, format) {
+        Ok(written) => {
+            for path in written {
+                println!("Wrote {}", path.display());
+            }
+            0
+        }
+        Err(err) => {
+            eprintln!("Could not convert {}: {}", input.display(), err);
+            1
+        }
+    }
+}
@@ -0,0 +1,767 @@
+/// This module converts files between the glTF containers. It works on the
+/// JSON of the file rather than the parsed document, so extensions and extras
+/// the inspector doesn't know about are written out unchanged.
+use std::{
+    collections::HashSet,
+    fs,
+    path::{Path, PathBuf},
+};
+
+use bevy::prelude::*;
+use serde_json::{json, Map, Value};
+
+use crate::{
+    ui::{right_section, RightPanel, StatusPanel},
+    InspectorState,
+};
+
+/// This plugin adds buttons writing the current file in each of the containers
+pub struct ConvertPlugin;
+
+impl Plugin for ConvertPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_convert_panel)
+            .add_system(convert_buttons);
+    }
+}
+
+/// The container a file is converted to
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Component)]
+pub enum Format {
+    /// A single .glb file with all buffers and images in its binary chunk
+    Glb,
+    /// A .gltf file with its buffers in .bin files and its images in image files
+    Separate,
+    /// A single .gltf file with all buffers and images in data URIs
+    Embedded,
+}
+
+impl Format {
+    /// The file the inspector writes a conversion of `input` to, next to it
+    pub fn output_path(&self, input: &Path) -> PathBuf {
+        let stem = input
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        let name = match self {
+            Format::Glb => format!("{}_packed.glb", stem),
+            Format::Separate => format!("{}_separate.gltf", stem),
+            Format::Embedded => format!("{}_embedded.gltf", stem),
+        };
+        input.with_file_name(name)
+    }
+}
+
+impl std::fmt::Display for Format {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Format::Glb => write!(f, "GLB"),
+            Format::Separate => write!(f, "glTF with external files"),
+            Format::Embedded => write!(f, "glTF with data URIs"),
+        }
+    }
+}
+
+/// A buffer or image read from the input file
+struct Blob {
+    data: Vec<u8>,
+    mime_type: Option<String>,
+}
+
+/// The JSON of a file together with the data of its buffers and images
+pub struct Source {
+    pub root: Value,
+    buffers: Vec<Vec<u8>>,
+    /// The images that are not stored in buffer views
+    images: Vec<Option<Blob>>,
+    /// The directory external files are relative to
+    base: PathBuf,
+}
+
+impl Source {
+    /// Reads a .gltf or .glb file with its buffers and images
+    pub fn read(input: &Path) -> Result<Self, String> {
+        let bytes = fs::read(input)
+            .map_err(|err| format!("Could not read {}: {}", input.display(), err))?;
+        let (json, mut bin) = if bytes.starts_with(b"glTF") {
+            let glb = gltf::binary::Glb::from_slice(&bytes).map_err(|err| err.to_string())?;
+            (glb.json.into_owned(), glb.bin.map(|bin| bin.into_owned()))
+        } else {
+            (bytes, None)
+        };
+        let root: Value = serde_json::from_slice(&json).map_err(|err| err.to_string())?;
+        if !root.is_object() {
+            return Err("The JSON is not an object".to_string());
+        }
+        let base = input.parent().unwrap_or_else(|| Path::new(""));
+
+        let mut buffers = vec![];
+        for buffer in array(&root, "buffers") {
+            let data = match buffer.get("uri").and_then(Value::as_str) {
+                Some(uri) => read_uri(base, uri)?.data,
+                None => bin
+                    .take()
+                    .ok_or("A buffer without URI refers to a missing binary chunk")?,
+            };
+            buffers.push(data);
+        }
+        // Images in buffer views already are part of a buffer
+        let mut images = vec![];
+        for image in array(&root, "images") {
+            images.push(match image.get("uri").and_then(Value::as_str) {
+                Some(uri) => Some(read_uri(base, uri)?),
+                None => None,
+            });
+        }
+        Ok(Self {
+            root,
+            buffers,
+            images,
+            base: base.to_path_buf(),
+        })
+    }
+
+    /// Appends a buffer and returns its index
+    pub fn add_buffer(&mut self, data: Vec<u8>) -> usize {
+        let index = self.add("buffers", json!({ "byteLength": data.len() }));
+        self.buffers.push(data);
+        index
+    }
+
+    /// Appends an object to a top-level array like `accessors` and returns its index
+    pub fn add(&mut self, key: &str, object: Value) -> usize {
+        let objects = self
+            .root
+            .as_object_mut()
+            .unwrap()
+            .entry(key)
+            .or_insert_with(|| Value::Array(vec![]))
+            .as_array_mut()
+            .unwrap();
+        objects.push(object);
+        objects.len() - 1
+    }
+}
+
+/// Writes the file at `input` to `output` in another container and returns
+/// the paths of all written files
+pub fn convert(input: &Path, output: &Path, format: Format) -> Result<Vec<PathBuf>, String> {
+    if same_file(input, output) {
+        return Err("The output would overwrite the input".to_string());
+    }
+    write_source(Source::read(input)?, output, format)
+}
+
+/// Writes a file in the given container and returns the paths of all written files
+pub fn write_source(source: Source, output: &Path, format: Format) -> Result<Vec<PathBuf>, String> {
+    let Source {
+        mut root,
+        mut buffers,
+        mut images,
+        base,
+    } = source;
+    match format {
+        Format::Glb => {
+            let bin = pack_buffers(&mut root, buffers, images);
+            let json = serde_json::to_vec(&root).map_err(|err| err.to_string())?;
+            let glb = gltf::binary::Glb {
+                header: gltf::binary::Header {
+                    magic: *b"glTF",
+                    version: 2,
+                    length: 0,
+                },
+                json: json.into(),
+                bin: bin.map(Into::into),
+            };
+            let bytes = glb.to_vec().map_err(|err| err.to_string())?;
+            write(output, &bytes)?;
+            Ok(vec![output.to_path_buf()])
+        }
+        Format::Embedded => {
+            for (buffer, data) in array_mut(&mut root, "buffers").iter_mut().zip(buffers) {
+                buffer["uri"] = data_uri("application/octet-stream", &data).into();
+            }
+            for (image, blob) in array_mut(&mut root, "images").iter_mut().zip(images) {
+                if let Some(blob) = blob {
+                    let mime_type = blob
+                        .mime_type
+                        .or_else(|| image_mime_type(&blob.data).map(str::to_string))
+                        .unwrap_or_else(|| "application/octet-stream".to_string());
+                    image["uri"] = data_uri(&mime_type, &blob.data).into();
+                }
+            }
+            write_json(output, &root)?;
+            Ok(vec![output.to_path_buf()])
+        }
+        Format::Separate => {
+            extract_view_images(&mut root, &mut buffers, &mut images);
+            let stem = output
+                .file_stem()
+                .map(|stem| stem.to_string_lossy().into_owned())
+                .unwrap_or_default();
+            let directory = output.parent().unwrap_or_else(|| Path::new(""));
+            let mut written = vec![];
+            let buffer_count = buffers.len();
+            for (index, (buffer, data)) in array_mut(&mut root, "buffers")
+                .iter_mut()
+                .zip(buffers)
+                .enumerate()
+            {
+                let uri = match buffer_count {
+                    1 => format!("{}.bin", stem),
+                    _ => format!("{}_{}.bin", stem, index),
+                };
+                written.push(directory.join(&uri));
+                write(&directory.join(&uri), &data)?;
+                buffer["uri"] = uri.into();
+            }
+            for (index, (image, blob)) in array_mut(&mut root, "images")
+                .iter_mut()
+                .zip(images)
+                .enumerate()
+            {
+                let blob = match blob {
+                    Some(it) => it,
+                    None => continue,
+                };
+                // External images keep their name, embedded ones are named after the file
+                let (uri, external) = match image["uri"].as_str() {
+                    Some(uri) if !uri.starts_with("data:") => (uri.to_string(), true),
+                    _ => {
+                        let extension = match blob
+                            .mime_type
+                            .as_deref()
+                            .or_else(|| image_mime_type(&blob.data))
+                        {
+                            Some("image/png") => "png",
+                            Some("image/jpeg") => "jpg",
+                            _ => "bin",
+                        };
+                        (format!("{}_image{}.{}", stem, index, extension), false)
+                    }
+                };
+                let path = directory.join(uri_path(&uri));
+                // Converting next to the input leaves its external images where they are
+                if !external || !same_file(&base.join(uri_path(&uri)), &path) {
+                    write(&path, &blob.data)?;
+                    written.push(path);
+                }
+                image["uri"] = uri.into();
+            }
+            write_json(output, &root)?;
+            written.insert(0, output.to_path_buf());
+            Ok(written)
+        }
+    }
+}
+
+/// Merges all buffers and images into the single buffer of a GLB file
+fn pack_buffers(
+    root: &mut Value,
+    buffers: Vec<Vec<u8>>,
+    images: Vec<Option<Blob>>,
+) -> Option<Vec<u8>> {
+    let mut bin = vec![];
+    let mut offsets = vec![];
+    for data in buffers {
+        offsets.push(bin.len());
+        bin.extend(data);
+        align(&mut bin);
+    }
+    // Buffer views, and extensions of buffer views like EXT_meshopt_compression,
+    // refer to buffers by index and offset
+    for view in array_mut(root, "bufferViews") {
+        visit_objects(view, &mut |object| {
+            if let Some(buffer) = object.get("buffer").and_then(Value::as_u64) {
+                let offset = object
+                    .get("byteOffset")
+                    .and_then(Value::as_u64)
+                    .unwrap_or(0);
+                let base = offsets.get(buffer as usize).copied().unwrap_or(0) as u64;
+                object.insert("buffer".to_string(), 0.into());
+                object.insert("byteOffset".to_string(), (base + offset).into());
+            }
+        });
+    }
+
+    for (index, blob) in images.into_iter().enumerate() {
+        let blob = match blob {
+            Some(it) => it,
+            None => continue,
+        };
+        let mime_type = blob
+            .mime_type
+            .clone()
+            .or_else(|| image_mime_type(&blob.data).map(str::to_string))
+            .unwrap_or_else(|| "application/octet-stream".to_string());
+        let view = json!({
+            "buffer": 0,
+            "byteOffset": bin.len(),
+            "byteLength": blob.data.len(),
+        });
+        bin.extend(blob.data);
+        align(&mut bin);
+        let views = root
+            .as_object_mut()
+            .unwrap()
+            .entry("bufferViews")
+            .or_insert_with(|| Value::Array(vec![]));
+        let views = views.as_array_mut().unwrap();
+        views.push(view);
+        let view_index = views.len() - 1;
+        if let Some(image) = root["images"][index].as_object_mut() {
+            image.remove("uri");
+            image.insert("bufferView".to_string(), view_index.into());
+            image.insert("mimeType".to_string(), mime_type.into());
+        }
+    }
+
+    if bin.is_empty() && array(root, "buffers").is_empty() {
+        return None;
+    }
+    // The first buffer keeps its name, extensions and extras
+    let mut buffer = array(root, "buffers")
+        .first()
+        .and_then(Value::as_object)
+        .cloned()
+        .unwrap_or_else(Map::new);
+    buffer.remove("uri");
+    buffer.insert("byteLength".to_string(), bin.len().into());
+    root["buffers"] = json!([buffer]);
+    Some(bin)
+}
+
+/// Moves the images stored in buffer views into separate blobs, so they can be
+/// written as image files. The views that nothing else refers to are removed
+/// together with their bytes.
+fn extract_view_images(root: &mut Value, buffers: &mut [Vec<u8>], images: &mut [Option<Blob>]) {
+    let mut extracted = vec![];
+    for (index, image) in array(root, "images").iter().enumerate() {
+        let view = match image.get("bufferView").and_then(Value::as_u64) {
+            Some(it) => it as usize,
+            None => continue,
+        };
+        let data =
+            view_range(root, view).and_then(|(buffer, range)| buffers.get(buffer)?.get(range));
+        if let (Some(data), Some(blob)) = (data, images.get_mut(index)) {
+            *blob = Some(Blob {
+                data: data.to_vec(),
+                mime_type: image
+                    .get("mimeType")
+                    .and_then(Value::as_str)
+                    .map(str::to_string),
+            });
+            extracted.push((index, view));
+        }
+    }
+    for (index, _) in &extracted {
+        if let Some(image) = array_mut(root, "images")[*index].as_object_mut() {
+            image.remove("bufferView");
+        }
+    }
+
+    let mut referenced = HashSet::new();
+    visit_objects(root, &mut |object| {
+        if let Some(view) = object.get("bufferView").and_then(Value::as_u64) {
+            referenced.insert(view as usize);
+        }
+    });
+    let mut unused: Vec<usize> = extracted
+        .into_iter()
+        .map(|(_, view)| view)
+        .filter(|view| !referenced.contains(view))
+        .collect();
+    unused.sort_unstable();
+    unused.dedup();
+    // Removing the last views first keeps the indices of the others valid
+    for view in unused.into_iter().rev() {
+        let (buffer, range) = match view_range(root, view) {
+            Some(it) => it,
+            None => continue,
+        };
+        // Cutting a multiple of 4 bytes keeps the following data aligned
+        let cut = range.start..range.start + range.len() / 4 * 4;
+        buffers[buffer].drain(cut.clone());
+        if let Some(length) = array(root, "buffers")[buffer]
+            .get("byteLength")
+            .and_then(Value::as_u64)
+        {
+            array_mut(root, "buffers")[buffer]["byteLength"] = (length - cut.len() as u64).into();
+        }
+        if let Some(views) = root["bufferViews"].as_array_mut() {
+            views.remove(view);
+        }
+        visit_objects(root, &mut |object| {
+            if let Some(index) = object.get("bufferView").and_then(Value::as_u64) {
+                if index as usize > view {
+                    object.insert("bufferView".to_string(), (index - 1).into());
+                }
+            }
+            let offset = object.get("byteOffset").and_then(Value::as_u64);
+            if let (Some(index), Some(offset)) =
+                (object.get("buffer").and_then(Value::as_u64), offset)
+            {
+                if index as usize == buffer && offset as usize >= cut.end {
+                    object.insert("byteOffset".to_string(), (offset - cut.len() as u64).into());
+                }
+            }
+        });
+    }
+}
+
+/// The buffer of a buffer view and the range of its bytes
+fn view_range(root: &Value, view: usize) -> Option<(usize, std::ops::Range<usize>)> {
+    let view = array(root, "bufferViews").get(view)?;
+    let buffer = view.get("buffer")?.as_u64()? as usize;
+    let start = view.get("byteOffset").and_then(Value::as_u64).unwrap_or(0) as usize;
+    let length = view.get("byteLength")?.as_u64()? as usize;
+    Some((buffer, start..start + length))
+}
+
+/// Calls `f` on every object of the JSON tree, except for the contents of extras
+fn visit_objects(value: &mut Value, f: &mut impl FnMut(&mut Map<String, Value>)) {
+    match value {
+        Value::Object(object) => {
+            f(object);
+            for (key, value) in object.iter_mut() {
+                if key != "extras" {
+                    visit_objects(value, f);
+                }
+            }
+        }
+        Value::Array(array) => array.iter_mut().for_each(|value| visit_objects(value, f)),
+        _ => (),
+    }
+}
+
+/// Whether both paths exist and point to the same file
+fn same_file(a: &Path, b: &Path) -> bool {
+    match (a.canonicalize(), b.canonicalize()) {
+        (Ok(a), Ok(b)) => a == b,
+        _ => false,
+    }
+}
+
+fn align(bin: &mut Vec<u8>) {
+    while !bin.len().is_multiple_of(4) {
+        bin.push(0);
+    }
+}
+
+fn array<'a>(root: &'a Value, key: &str) -> &'a [Value] {
+    root.get(key)
+        .and_then(Value::as_array)
+        .map(Vec::as_slice)
+        .unwrap_or_default()
+}
+
+fn array_mut<'a>(root: &'a mut Value, key: &str) -> &'a mut [Value] {
+    root.get_mut(key)
+        .and_then(Value::as_array_mut)
+        .map(Vec::as_mut_slice)
+        .unwrap_or_default()
+}
+
+/// Reads the data of a data URI, or of a file relative to `base`
+fn read_uri(base: &Path, uri: &str) -> Result<Blob, String> {
+    if let Some(data) = uri.strip_prefix("data:") {
+        let (header, payload) = data.split_once(',').ok_or("Malformed data URI")?;
+        let (mime_type, is_base64) = match header.strip_suffix(";base64") {
+            Some(mime_type) => (mime_type, true),
+            None => (header, false),
+        };
+        let data = if is_base64 {
+            base64::decode(payload).map_err(|err| format!("Invalid data URI: {}", err))?
+        } else {
+            percent_decode(payload)
+        };
+        return Ok(Blob {
+            data,
+            mime_type: (!mime_type.is_empty()).then(|| mime_type.to_string()),
+        });
+    }
+    let path = base.join(uri_path(uri));
+    let data =
+        fs::read(&path).map_err(|err| format!("Could not read {}: {}", path.display(), err))?;
+    let mime_type = match path
+        .extension()
+        .and_then(|extension| extension.to_str())
+        .map(str::to_ascii_lowercase)
+        .as_deref()
+    {
+        Some("png") => Some("image/png".to_string()),
+        Some("jpg") | Some("jpeg") => Some("image/jpeg".to_string()),
+        _ => None,
+    };
+    Ok(Blob { data, mime_type })
+}
+
+/// Detects the image formats of the specification by their signature
+fn image_mime_type(data: &[u8]) -> Option<&'static str> {
+    if data.starts_with(b"\x89PNG\r\n\x1a\n") {
+        Some("image/png")
+    } else if data.starts_with(&[0xff, 0xd8, 0xff]) {
+        Some("image/jpeg")
+    } else {
+        None
+    }
+}
+
+fn data_uri(mime_type: &str, data: &[u8]) -> String {
+    format!("data:{};base64,{}", mime_type, base64::encode(data))
+}
+
+/// Decodes the escaped characters of a URI, e.g. `%20`
+fn percent_decode(uri: &str) -> Vec<u8> {
+    let bytes = uri.as_bytes();
+    let mut decoded = vec![];
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = (bytes[index] == b'%')
+            .then(|| uri.get(index + 1..index + 3))
+            .flatten()
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match escaped {
+            Some(byte) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            None => {
+                decoded.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    decoded
+}
+
+/// The path of a file relative to the glTF file, given by its URI
+fn uri_path(uri: &str) -> PathBuf {
+    PathBuf::from(String::from_utf8_lossy(&percent_decode(uri)).into_owned())
+}
+
+fn write(path: &Path, data: &[u8]) -> Result<(), String> {
+    if let Some(directory) = path.parent().filter(|it| !it.as_os_str().is_empty()) {
+        fs::create_dir_all(directory).map_err(|err| err.to_string())?;
+    }
+    fs::write(path, data).map_err(|err| format!("Could not write {}: {}", path.display(), err))
+}
+
+fn write_json(path: &Path, root: &Value) -> Result<(), String> {
+    let json = serde_json::to_string_pretty(root).map_err(|err| err.to_string())?;
+    write(path, json.as_bytes())
+}
+
+fn spawn_convert_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    right_panel_query: Query<Entity, With<RightPanel>>,
+) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 16.0,
+        color: Color::WHITE,
+    };
+    let panel = commands
+        .spawn_bundle(right_section())
+        .with_children(|parent| {
+            parent.spawn_bundle(TextBundle {
+                text: Text::with_section("Save as:", text_style.clone(), Default::default()),
+                ..Default::default()
+            });
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    for (label, format) in [
+                        ("[GLB] ", Format::Glb),
+                        ("[glTF + files] ", Format::Separate),
+                        ("[glTF embedded]", Format::Embedded),
+                    ] {
+                        parent
+                            .spawn_bundle(TextBundle {
+                                text: Text::with_section(
+                                    label,
+                                    text_style.clone(),
+                                    Default::default(),
+                                ),
+                                ..Default::default()
+                            })
+                            .insert(Interaction::default())
+                            .insert(format);
+                    }
+                });
+        })
+        .id();
+    commands.entity(right_panel_query.single()).add_child(panel);
+}
+
+/// Writes the current file next to itself in the container of the clicked button
+fn convert_buttons(
+    interaction_query: Query<(&Interaction, &Format), Changed<Interaction>>,
+    state: Res<InspectorState>,
+    mut status_query: Query<&mut Text, With<StatusPanel>>,
+) {
+    let file = match state.current_file {
+        Some(it) => it,
+        _ => return,
+    };
+    for (_, format) in interaction_query
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Clicked)
+    {
+        let input = Path::new("assets").join(&file.path);
+        let output = format.output_path(&input);
+        let status = match convert(&input, &output, *format) {
+            Ok(written) => {
+                info!("Converted {} to {}", input.display(), format);
+                format!("Wrote {} files, {}", written.len(), output.display())
+            }
+            Err(err) => {
+                error!("Could not convert {}: {}", input.display(), err);
+                format!("Could not convert: {}", err)
+            }
+        };
+        status_query.single_mut().sections[0].value = status;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn percent_decoding_keeps_every_byte() {
+        assert_eq!(percent_decode("a%20b%2Fc"), b"a b/c");
+        assert_eq!(percent_decode("%ff%00%C3%A9"), [0xff, 0x00, 0xc3, 0xa9]);
+        // Incomplete escapes are kept as they are
+        assert_eq!(percent_decode("100%"), b"100%");
+        assert_eq!(percent_decode("%zz"), b"%zz");
+    }
+
+    #[test]
+    fn reads_data_uris() {
+        let blob = read_uri(
+            Path::new(""),
+            "data:application/gltf-buffer;base64,AAEC/w==",
+        )
+        .unwrap();
+        assert_eq!(blob.data, [0, 1, 2, 255]);
+        assert_eq!(blob.mime_type.as_deref(), Some("application/gltf-buffer"));
+        let blob = read_uri(Path::new(""), "data:,%80%FFx").unwrap();
+        assert_eq!(blob.data, [0x80, 0xff, b'x']);
+        assert_eq!(blob.mime_type, None);
+        assert!(read_uri(Path::new(""), "data:application/octet-stream;base64").is_err());
+        assert!(read_uri(Path::new(""), "data:;base64,***").is_err());
+    }
+
+    #[test]
+    fn packs_buffers_and_images_into_one_aligned_buffer() {
+        let mut root = json!({
+            "buffers": [{ "byteLength": 3, "name": "first" }, { "byteLength": 4 }],
+            "bufferViews": [
+                { "buffer": 0, "byteLength": 3 },
+                {
+                    "buffer": 1,
+                    "byteOffset": 2,
+                    "byteLength": 2,
+                    "extras": { "buffer": 1, "byteOffset": 2 },
+                },
+            ],
+            "images": [{ "uri": "texture.png" }, { "bufferView": 0, "mimeType": "image/png" }],
+        });
+        let png = b"\x89PNG\r\n\x1a\n".to_vec();
+        let bin = pack_buffers(
+            &mut root,
+            vec![vec![1, 2, 3], vec![4, 5, 6, 7]],
+            vec![
+                Some(Blob {
+                    data: png.clone(),
+                    mime_type: None,
+                }),
+                None,
+            ],
+        )
+        .unwrap();
+        assert_eq!(&bin[..8], [1, 2, 3, 0, 4, 5, 6, 7]);
+        assert_eq!(&bin[8..], png);
+        assert_eq!(
+            root["buffers"],
+            json!([{ "byteLength": 16, "name": "first" }])
+        );
+        assert_eq!(root["bufferViews"][1]["buffer"], 0);
+        assert_eq!(root["bufferViews"][1]["byteOffset"], 6);
+        // Extras are copied unchanged
+        assert_eq!(root["bufferViews"][1]["extras"]["byteOffset"], 2);
+        assert_eq!(
+            root["images"][0],
+            json!({ "bufferView": 2, "mimeType": "image/png" })
+        );
+        assert_eq!(root["bufferViews"][2]["byteOffset"], 8);
+        assert_eq!(root["images"][1]["bufferView"], 0);
+    }
+
+    #[test]
+    fn extracts_images_from_buffer_views() {
+        let mut data: Vec<u8> = (0..12).collect();
+        data.extend(b"image!\0\0");
+        data.extend([20, 21, 22, 23]);
+        let mut root = json!({
+            "buffers": [{ "byteLength": 24 }],
+            "bufferViews": [
+                { "buffer": 0, "byteLength": 12 },
+                { "buffer": 0, "byteOffset": 12, "byteLength": 6 },
+                { "buffer": 0, "byteOffset": 20, "byteLength": 4 },
+            ],
+            "accessors": [{ "bufferView": 0 }, { "bufferView": 2 }],
+            "images": [{ "bufferView": 1, "mimeType": "image/png" }],
+        });
+        let mut buffers = vec![data];
+        let mut images = vec![None];
+        extract_view_images(&mut root, &mut buffers, &mut images);
+
+        let blob = images[0].as_ref().unwrap();
+        assert_eq!(blob.data, b"image!");
+        assert_eq!(blob.mime_type.as_deref(), Some("image/png"));
+        assert_eq!(root["images"][0], json!({ "mimeType": "image/png" }));
+        assert_eq!(root["accessors"][1]["bufferView"], 1);
+        assert_eq!(root["bufferViews"].as_array().unwrap().len(), 2);
+        assert_eq!(root["bufferViews"][1]["byteOffset"], 16);
+        assert_eq!(root["buffers"][0]["byteLength"], 20);
+        assert_eq!(buffers[0].len(), 20);
+        assert_eq!(&buffers[0][16..], [20, 21, 22, 23]);
+    }
+
+    #[test]
+    fn keeps_image_views_that_accessors_use() {
+        let mut root = json!({
+            "buffers": [{ "byteLength": 4 }],
+            "bufferViews": [{ "buffer": 0, "byteLength": 4 }],
+            "accessors": [{ "bufferView": 0 }],
+            "images": [{ "bufferView": 0, "mimeType": "image/jpeg" }],
+        });
+        let mut buffers = vec![vec![1, 2, 3, 4]];
+        let mut images = vec![None];
+        extract_view_images(&mut root, &mut buffers, &mut images);
+        assert_eq!(images[0].as_ref().unwrap().data, [1, 2, 3, 4]);
+        assert_eq!(root["bufferViews"].as_array().unwrap().len(), 1);
+        assert_eq!(buffers[0].len(), 4);
+    }
+
+    #[test]
+    fn reads_escaped_file_names() {
+        let directory = std::env::temp_dir().join("gltf_inspector_read_uri");
+        fs::create_dir_all(&directory).unwrap();
+        fs::write(directory.join("a texture.png"), [1, 2, 3]).unwrap();
+        let blob = read_uri(&directory, "a%20texture.png").unwrap();
+        assert_eq!(blob.data, [1, 2, 3]);
+        assert_eq!(blob.mime_type.as_deref(), Some("image/png"));
+        assert!(read_uri(&directory, "missing.bin").is_err());
+    }
+}
@@ -0,0 +1,373 @@
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::Serialize;
+
+use crate::{
+    file_picker::FileChangedEvent, utils::format_bytes, validation::hierarchy_issues,
+    InspectorState,
+};
+
+/// Systems reading the [LoadedDocument] after a [FileChangedEvent] need to run
+/// after it has been replaced, otherwise they would see the previous file
+#[derive(Clone, Hash, Debug, PartialEq, Eq, SystemLabel)]
+pub struct DocumentLoading;
+
+/// The glTF document of the current file, together with its buffers and images.
+/// It is read from disk once per [FileChangedEvent] instead of every frame.
+#[derive(Default)]
+pub struct LoadedDocument {
+    pub document: Option<gltf::Document>,
+    pub buffers: Vec<gltf::buffer::Data>,
+    pub images: Vec<gltf::image::Data>,
+    /// How the file stores its JSON and where its buffers and images come from
+    pub layout: Option<FileLayout>,
+}
+
+impl LoadedDocument {
+    /// Reads a .gltf or .glb file with its buffers and images
+    pub fn import(path: &Path) -> Result<Self, gltf::Error> {
+        let (document, buffers, images) = gltf::import(path)?;
+        let bytes = std::fs::read(path).map_err(gltf::Error::Io)?;
+        Ok(LoadedDocument {
+            layout: Some(FileLayout::new(&bytes, &document)),
+            document: Some(document),
+            buffers,
+            images,
+        })
+    }
+
+    /// Whether the elements of an accessor lie within its buffer views, and the
+    /// views within the loaded buffers. The readers of the gltf crate panic otherwise.
+    pub fn accessor_in_bounds(&self, accessor: &gltf::Accessor) -> bool {
+        let in_bounds = |view: gltf::buffer::View, offset: usize, count: usize, size: usize| {
+            let end = match count {
+                0 => offset,
+                count => offset + view.stride().unwrap_or(size) * (count - 1) + size,
+            };
+            let buffer_length = self
+                .buffers
+                .get(view.buffer().index())
+                .map(|data| data.len())
+                .unwrap_or_default();
+            end <= view.length() && view.offset() + view.length() <= buffer_length
+        };
+        let dense = accessor.view().is_none_or(|view| {
+            in_bounds(view, accessor.offset(), accessor.count(), accessor.size())
+        });
+        let sparse = accessor.sparse().is_none_or(|sparse| {
+            let count = sparse.count() as usize;
+            let (indices, values) = (sparse.indices(), sparse.values());
+            in_bounds(
+                indices.view(),
+                indices.offset() as usize,
+                count,
+                indices.index_type().size(),
+            ) && in_bounds(
+                values.view(),
+                values.offset() as usize,
+                count,
+                accessor.size(),
+            )
+        });
+        dense && sparse
+    }
+
+    /// Loads a document from its JSON, with buffers given as data URIs
+    #[cfg(test)]
+    pub fn from_json(json: &serde_json::Value) -> Self {
+        let (document, buffers, images) = gltf::import_slice(json.to_string().as_bytes())
+            .expect("the test document should be valid");
+        LoadedDocument {
+            document: Some(document),
+            buffers,
+            images,
+            layout: None,
+        }
+    }
+}
+
+/// The kind of file, with the sizes of its parts in bytes
+#[derive(Clone, Copy, Debug, Serialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Container {
+    /// A JSON file
+    Gltf { size: usize },
+    /// A binary file with a JSON chunk, usually followed by a binary chunk
+    Glb {
+        size: usize,
+        json_chunk: usize,
+        bin_chunk: Option<usize>,
+    },
+}
+
+/// Where the data of a buffer or image is stored
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Storage {
+    /// The binary chunk of a GLB file
+    BinChunk,
+    /// A buffer view, for images
+    BufferView,
+    /// A base64 data URI inside the JSON
+    DataUri,
+    /// A separate file next to the glTF file
+    External,
+}
+
+impl std::fmt::Display for Storage {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Storage::BinChunk => write!(f, "BIN chunk"),
+            Storage::BufferView => write!(f, "buffer view"),
+            Storage::DataUri => write!(f, "data URI"),
+            Storage::External => write!(f, "external"),
+        }
+    }
+}
+
+/// A buffer or image of the file
+#[derive(Clone, Debug, Serialize)]
+pub struct Resource {
+    /// The JSON pointer to the buffer or image, e.g. `/images/2`
+    pub pointer: String,
+    pub storage: Storage,
+    /// The URI of an external file
+    pub uri: Option<String>,
+    /// The size of buffers and of images in buffer views
+    pub size: Option<usize>,
+}
+
+/// The container of a file and the buffers and images it embeds or references
+#[derive(Clone, Debug, Serialize)]
+pub struct FileLayout {
+    pub container: Container,
+    pub resources: Vec<Resource>,
+}
+
+impl FileLayout {
+    /// Describes the file read from `bytes`
+    pub fn new(bytes: &[u8], document: &gltf::Document) -> Self {
+        let container = match gltf::binary::Glb::from_slice(bytes) {
+            Ok(glb) if bytes.starts_with(b"glTF") => Container::Glb {
+                size: bytes.len(),
+                json_chunk: glb.json.len(),
+                bin_chunk: glb.bin.map(|bin| bin.len()),
+            },
+            _ => Container::Gltf { size: bytes.len() },
+        };
+        let uri_storage = |uri: &str| match uri.starts_with("data:") {
+            true => (Storage::DataUri, None),
+            false => (Storage::External, Some(uri.to_string())),
+        };
+        let buffers = document.buffers().map(|buffer| {
+            let (storage, uri) = match buffer.source() {
+                gltf::buffer::Source::Bin => (Storage::BinChunk, None),
+                gltf::buffer::Source::Uri(uri) => uri_storage(uri),
+            };
+            Resource {
+                pointer: format!("/buffers/{}", buffer.index()),
+                storage,
+                uri,
+                size: Some(buffer.length()),
+            }
+        });
+        let images = document.images().map(|image| {
+            let (storage, uri, size) = match image.source() {
+                gltf::image::Source::View { view, .. } => {
+                    (Storage::BufferView, None, Some(view.length()))
+                }
+                gltf::image::Source::Uri { uri, .. } => {
+                    let (storage, uri) = uri_storage(uri);
+                    (storage, uri, None)
+                }
+            };
+            Resource {
+                pointer: format!("/images/{}", image.index()),
+                storage,
+                uri,
+                size,
+            }
+        });
+        FileLayout {
+            container,
+            resources: buffers.chain(images).collect(),
+        }
+    }
+
+    /// The container and how many buffers and images are stored in binary,
+    /// as data URIs or in external files, as lines of text
+    pub fn to_text(&self) -> String {
+        let container = match self.container {
+            Container::Gltf { size } => format!("glTF JSON, {}", format_bytes(size)),
+            Container::Glb {
+                size,
+                json_chunk,
+                bin_chunk,
+            } => format!(
+                "GLB, {}: JSON chunk {}, BIN chunk {}",
+                format_bytes(size),
+                format_bytes(json_chunk),
+                bin_chunk
+                    .map(format_bytes)
+                    .unwrap_or_else(|| "none".to_string())
+            ),
+        };
+        let mut lines = vec![container];
+        for kind in ["buffers", "images"] {
+            let resources: Vec<&Resource> = self
+                .resources
+                .iter()
+                .filter(|resource| resource.pointer.starts_with(&format!("/{}/", kind)))
+                .collect();
+            if resources.is_empty() {
+                continue;
+            }
+            let count = |storage: Storage| {
+                resources
+                    .iter()
+                    .filter(|resource| resource.storage == storage)
+                    .count()
+            };
+            lines.push(format!(
+                "{} {}: {} binary, {} data URIs, {} external files",
+                resources.len(),
+                kind,
+                count(Storage::BinChunk) + count(Storage::BufferView),
+                count(Storage::DataUri),
+                count(Storage::External)
+            ));
+        }
+        lines.join("\n")
+    }
+}
+
+/// A file that could not be loaded
+pub struct LoadError {
+    pub path: String,
+    /// What kind of error occurred
+    pub kind: &'static str,
+    pub message: String,
+}
+
+/// The files that could not be loaded since the start, at most one entry per file
+#[derive(Default)]
+pub struct LoadErrors {
+    pub errors: Vec<LoadError>,
+}
+
+pub struct DocumentPlugin;
+
+impl Plugin for DocumentPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LoadedDocument>()
+            .init_resource::<LoadErrors>()
+            .add_system(load_document.label(DocumentLoading));
+    }
+}
+
+fn load_document(
+    mut reader: EventReader<FileChangedEvent>,
+    state: Res<InspectorState>,
+    mut loaded: ResMut<LoadedDocument>,
+    mut load_errors: ResMut<LoadErrors>,
+) {
+    if reader.iter().next().is_none() {
+        return;
+    }
+    let file = match state.current_file {
+        Some(it) => it,
+        _ => {
+            *loaded = LoadedDocument::default();
+            return;
+        }
+    };
+    let path = Path::new("assets").join(&file.path);
+    let result = match LoadedDocument::import(&path) {
+        Ok(loaded) => {
+            let document = loaded.document.as_ref().unwrap();
+            let unsupported: Vec<&str> = document
+                .extensions_required()
+                .filter(|name| !gltf::json::extensions::ENABLED_EXTENSIONS.contains(name))
+                .collect();
+            // Drawing a node that is its own ancestor would never end
+            let hierarchy: Vec<String> = hierarchy_issues(document)
+                .iter()
+                .map(|issue| issue.to_string())
+                .collect();
+            if !unsupported.is_empty() {
+                Err(("Unsupported extension", unsupported.join(", ")))
+            } else if !hierarchy.is_empty() {
+                Err(("Invalid node hierarchy", hierarchy.join("\n")))
+            } else {
+                Ok(loaded)
+            }
+        }
+        Err(err) => Err((error_kind(&err), describe_error(&err, &path))),
+    };
+    *loaded = match result {
+        Ok(document) => document,
+        Err((kind, message)) => {
+            let error = LoadError {
+                path: file.path.clone(),
+                kind,
+                message,
+            };
+            error!("Could not load {}: {}", error.path, error.message);
+            load_errors.errors.retain(|it| it.path != error.path);
+            load_errors.errors.push(error);
+            LoadedDocument::default()
+        }
+    };
+
+    if let (Some(file), Some(_)) = (state.current_file, &loaded.document) {
+        info!(
+            "Loaded {} with {} buffers ({} bytes) and {} images",
+            file.path,
+            loaded.buffers.len(),
+            loaded
+                .buffers
+                .iter()
+                .map(|buffer| buffer.len())
+                .sum::<usize>(),
+            loaded.images.len()
+        );
+    }
+}
+
+fn error_kind(err: &gltf::Error) -> &'static str {
+    match err {
+        gltf::Error::Io(_) => "File not readable",
+        gltf::Error::Deserialize(_) => "Invalid JSON",
+        gltf::Error::Validation(_) => "Invalid glTF",
+        gltf::Error::Binary(_) | gltf::Error::MissingBlob => "Invalid GLB",
+        gltf::Error::Base64(_) | gltf::Error::BufferLength { .. } => "Invalid buffer",
+        gltf::Error::Image(_) | gltf::Error::UnsupportedImageEncoding => "Invalid image",
+        _ => "Unsupported file",
+    }
+}
+
+/// The message of the error, naming the missing buffers and images for I/O
+/// errors, as those don't include the path of the file that could not be read
+fn describe_error(err: &gltf::Error, path: &Path) -> String {
+    let mut message = err.to_string();
+    if let (gltf::Error::Io(_), Ok(gltf)) = (err, gltf::Gltf::open(path)) {
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        let uris = gltf
+            .buffers()
+            .filter_map(|buffer| match buffer.source() {
+                gltf::buffer::Source::Uri(uri) => Some(uri),
+                gltf::buffer::Source::Bin => None,
+            })
+            .chain(gltf.images().filter_map(|image| match image.source() {
+                gltf::image::Source::Uri { uri, .. } => Some(uri),
+                gltf::image::Source::View { .. } => None,
+            }));
+        for uri in uris {
+            if !uri.starts_with("data:") && !base.join(uri).exists() {
+                message.push_str(&format!("\nMissing: {}", uri));
+            }
+        }
+    }
+    message
+}
@@ -1,4 +1,7 @@
+use std::path::Path;
+
 use bevy::{
+    asset::AssetPath,
     gltf::{GltfMesh, GltfNode, GltfPrimitive},
     prelude::*,
 };
@@ -6,22 +9,47 @@ use bevy_mod_picking::PickableBundle;
 use gltf::{Node, Primitive};
 
 use crate::{
+    document::LoadedDocument,
     hierarchy::{MeshIndex, NodeIndex, PrimitiveIdentifier},
-    utils::{get_current_scene, get_gltf},
+    scene_picker::{SceneChangedEvent, SceneSelection},
+    skinning::Deformation,
+    utils::{get_current_scene, get_gltf, node_bounds, scene_bounds},
     InspectorState,
 };
 
+/// How the parts of a model are moved apart
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum ExplosionMode {
+    /// Every part moves outward along the vector from the scene center to its own center
+    #[default]
+    Radial,
+    /// Every part is offset by its sibling index along the file's explosion scale
+    Linear,
+}
+
+impl std::fmt::Display for ExplosionMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ExplosionMode::Radial => write!(f, "radial"),
+            ExplosionMode::Linear => write!(f, "linear"),
+        }
+    }
+}
+
 #[derive(Debug, PartialEq, Eq)]
 enum DrawingStatus {
-    Changed, // Some drawing parameter has changes
+    Changed, // Another file has been opened
     Drawing, // Started redrawing
     Drawn,   // No changes needed
 }
 
-/// When the inspector state is changed, this module updates the drawn model by
+/// When another file or scene is opened, this module replaces the drawn model by
 /// 1. Detecting a change has happenend
 /// 2. Removing the existing entity tree representing the GLTF model
-/// 3. Re-drawing the model with the new changes
+/// 3. Drawing the model of the new file
+///
+/// Changes to the explosion or animated poses only move the already drawn nodes
+/// 4. Updating the transforms of the nodes from their current poses
 ///
 /// For this to work consistently, I needed to order the stages.
 /// This can be done by labeling the systems and establishing an
@@ -33,6 +61,7 @@ pub enum DrawingOrder {
     DetectChange,
     RemoveExisitingScene,
     DrawNewScene,
+    UpdateExplosion,
 }
 
 struct DrawingState {
@@ -52,7 +81,12 @@ pub struct ExplosionPlugin;
 impl Plugin for ExplosionPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<DrawingState>()
-            .add_system(detect_changes.label(DrawingOrder::DetectChange))
+            .init_resource::<DefaultMaterial>()
+            .add_system(
+                detect_changes
+                    .label(DrawingOrder::DetectChange)
+                    .after(SceneSelection),
+            )
             .add_system(
                 remove_existing_scene
                     .label(DrawingOrder::RemoveExisitingScene)
@@ -62,16 +96,89 @@ impl Plugin for ExplosionPlugin {
                 spawn_gltf_objects
                     .label(DrawingOrder::DrawNewScene)
                     .after(DrawingOrder::RemoveExisitingScene),
+            )
+            .add_system(
+                update_explosion
+                    .label(DrawingOrder::UpdateExplosion)
+                    .after(DrawingOrder::DrawNewScene),
             );
     }
 }
 
-fn detect_changes(state: Res<InspectorState>, mut drawing_state: ResMut<DrawingState>) {
-    if state.is_changed() {
+fn detect_changes(
+    mut reader: EventReader<SceneChangedEvent>,
+    mut drawing_state: ResMut<DrawingState>,
+) {
+    if reader.iter().next().is_some() {
         drawing_state.status = DrawingStatus::Changed;
     }
 }
 
+/// Stores how a node is moved by the explosion, so its transform can be
+/// updated in place instead of redrawing the model
+#[derive(Component)]
+pub struct ExplodedPart {
+    /// The transform of the node as defined in the file
+    pub original: Transform,
+    /// The transform of the node before the explosion is applied, which
+    /// differs from the original one while an animation is played
+    pub pose: Transform,
+    /// The translation per unit of explosion factor in radial mode
+    radial_offset: Vec3,
+    /// The translation per unit of explosion factor in linear mode
+    linear_offset: Vec3,
+}
+
+impl ExplodedPart {
+    /// The translation the explosion adds to the pose of the node
+    pub fn offset(&self, state: &InspectorState) -> Vec3 {
+        let offset = match state.explosion_mode {
+            ExplosionMode::Radial => self.radial_offset,
+            ExplosionMode::Linear => self.linear_offset,
+        };
+        state.explosion_factor * offset
+    }
+
+    fn transform(&self, state: &InspectorState) -> Transform {
+        self.pose
+            .with_translation(self.pose.translation + self.offset(state))
+    }
+}
+
+/// Moves the drawn nodes when the explosion factor or mode, or their pose has changed
+fn update_explosion(
+    state: Res<InspectorState>,
+    mut query: Query<(&mut Transform, &ExplodedPart, ChangeTrackers<ExplodedPart>)>,
+) {
+    for (mut transform, part, tracker) in query.iter_mut() {
+        if state.is_changed() || tracker.is_changed() {
+            *transform = part.transform(&state);
+        }
+    }
+}
+
+/// The material of primitives that don't reference one, which is the default
+/// material of the glTF specification: white, fully metallic and fully rough
+pub struct DefaultMaterial(Handle<StandardMaterial>);
+
+impl FromWorld for DefaultMaterial {
+    fn from_world(world: &mut World) -> Self {
+        let mut materials = world
+            .get_resource_mut::<Assets<StandardMaterial>>()
+            .unwrap();
+        DefaultMaterial(materials.add(StandardMaterial {
+            base_color: Color::WHITE,
+            metallic: 1.0,
+            perceptual_roughness: 1.0,
+            ..Default::default()
+        }))
+    }
+}
+
+/// Tags a drawn primitive that has no material and is drawn with the [DefaultMaterial]
+#[derive(Component)]
+pub struct FallbackMaterial;
+
 /// A tag struct, that allows me to filter for the
 /// Top-Level entity representing the currently shown
 /// GLTF-Model
@@ -92,13 +199,16 @@ fn remove_existing_scene(
     }
 }
 
+#[allow(clippy::too_many_arguments)]
 fn spawn_gltf_objects(
     mut commands: Commands,
     state: Res<InspectorState>,
+    loaded: Res<LoadedDocument>,
     mut drawing_state: ResMut<DrawingState>,
     assets: Res<AssetServer>,
     assets_gltfnode: Res<Assets<GltfNode>>,
     assets_gltfmesh: Res<Assets<GltfMesh>>,
+    default_material: Res<DefaultMaterial>,
 ) {
     if drawing_state.status == DrawingStatus::Drawn {
         return;
@@ -111,14 +221,22 @@ fn spawn_gltf_objects(
     drawing_state.status = DrawingStatus::Drawing;
     // if the GLTF has loaded, we can navigate its contents
     let mut node_handles = vec![];
-    let gltf = get_gltf(&state);
-    let scene = match gltf.as_ref().map(|gltf| get_current_scene(&state, gltf)) {
+    let roots = match get_gltf(&loaded).map(|gltf| get_current_scene(&state, gltf)) {
         Some(it) => it,
         _ => return,
     };
-    for gltf_node in scene.nodes() {
-        let path = format!("{}#Node{}", file.path, gltf_node.index());
-        let node_handle: Handle<GltfNode> = assets.load(path.as_str());
+    let scene_frame = PartFrame {
+        world: Mat4::IDENTITY,
+        center: scene_bounds(roots.clone())
+            .map(|bounds| bounds.center())
+            .unwrap_or_default(),
+    };
+    for gltf_node in roots {
+        // Built from parts, since paths passed on the command line may
+        // contain a '#', which would be taken for the start of the label
+        let label = format!("Node{}", gltf_node.index());
+        let node_handle: Handle<GltfNode> =
+            assets.load(AssetPath::new_ref(Path::new(&file.path), Some(&label)));
         node_handles.push((node_handle, gltf_node));
     }
     if node_handles
@@ -137,29 +255,74 @@ fn spawn_gltf_objects(
         .with_children(|parent| {
             for (index, node) in node_handles.into_iter().enumerate() {
                 if let Some(bevy_node) = assets_gltfnode.get(&node.0) {
-                    draw_node(parent, (bevy_node, node.1), &assets_gltfmesh, &state, index);
+                    draw_node(
+                        parent,
+                        (bevy_node, node.1),
+                        &assets_gltfmesh,
+                        &default_material,
+                        &state,
+                        index,
+                        scene_frame,
+                    );
                 }
             }
         });
     drawing_state.status = DrawingStatus::Drawn;
 }
 
+/// The unexploded world transform of a part and the center of its bounds,
+/// which its children are exploded away from
+#[derive(Clone, Copy)]
+struct PartFrame {
+    world: Mat4,
+    center: Vec3,
+}
+
+/// Computes how a node is moved by the explosion in the space of its parent,
+/// and the frame its own children are exploded relative to
+fn exploded_part(
+    node: (&GltfNode, &Node),
+    state: &InspectorState,
+    part_index: usize,
+    parent: PartFrame,
+) -> (ExplodedPart, PartFrame) {
+    let world = parent.world * Mat4::from_cols_array_2d(&node.1.transform().matrix());
+    // Nodes without geometry below them stay where they are
+    let center = node_bounds(node.1.clone(), parent.world)
+        .map(|bounds| bounds.center())
+        .unwrap_or(parent.center);
+    // The parents were already moved by their share of the offset
+    let radial_offset = parent
+        .world
+        .inverse()
+        .transform_vector3(center - parent.center);
+    let part = ExplodedPart {
+        original: node.0.transform,
+        pose: node.0.transform,
+        radial_offset,
+        linear_offset: part_index as f32 * state.current_file.unwrap().explosion_scale,
+    };
+    (part, PartFrame { world, center })
+}
+
 // This code was originally inspired by https://github.com/bevyengine/bevy/blob/3f6068da3db8038ab69706a40ba714cfd836238d/crates/bevy_gltf/src/loader.rs#L456-L616
 // But the modificiations made to tag the nodes and meshes hide the similarities in structure
 fn draw_node(
     commands: &mut ChildBuilder,
     node: (&GltfNode, Node),
     assets_gltfmesh: &Res<Assets<GltfMesh>>,
+    default_material: &DefaultMaterial,
     state: &Res<InspectorState>,
     part_index: usize,
+    parent_frame: PartFrame,
 ) -> Entity {
-    let new_translation = node.0.transform.translation
-        + state.explosion_factor * part_index as f32 * state.current_file.unwrap().explosion_scale;
+    let (part, frame) = exploded_part((node.0, &node.1), state, part_index, parent_frame);
     commands
         .spawn()
-        .insert(node.0.transform.with_translation(new_translation))
+        .insert(part.transform(state))
         .insert(GlobalTransform::default())
         .insert(NodeIndex(node.1.index()))
+        .insert(part)
         .with_children(|parent| {
             node.0
                 .mesh
@@ -170,7 +333,7 @@ fn draw_node(
                         .get(handle.0)
                         .map(|bevy_mesh_ref| (bevy_mesh_ref, handle.1))
                 })
-                .map(|mesh| draw_mesh(parent, mesh));
+                .map(|mesh| draw_mesh(parent, mesh, &node.1, default_material));
 
             node.0
                 .children
@@ -178,37 +341,69 @@ fn draw_node(
                 .zip(node.1.children())
                 .enumerate()
                 .for_each(|(index, node)| {
-                    draw_node(parent, node, assets_gltfmesh, state, index);
+                    draw_node(
+                        parent,
+                        node,
+                        assets_gltfmesh,
+                        default_material,
+                        state,
+                        index,
+                        frame,
+                    );
                 });
         })
         .id()
 }
 
-fn draw_mesh(parent: &mut ChildBuilder, mesh: (&GltfMesh, gltf::Mesh)) -> Vec<Entity> {
+fn draw_mesh(
+    parent: &mut ChildBuilder,
+    mesh: (&GltfMesh, gltf::Mesh),
+    node: &Node,
+    default_material: &DefaultMaterial,
+) -> Vec<Entity> {
     mesh.0
         .primitives
         .iter()
         .zip(mesh.1.primitives())
-        .map(|prim| draw_primitive(parent, prim, MeshIndex(mesh.1.index())))
+        .map(|prim| {
+            draw_primitive(
+                parent,
+                prim,
+                MeshIndex(mesh.1.index()),
+                Deformation::of_node(node),
+                default_material,
+            )
+        })
         .collect()
 }
 fn draw_primitive(
     parent: &mut ChildBuilder,
     primitive: (&GltfPrimitive, Primitive),
     mesh_index: MeshIndex,
+    deformation: Option<Deformation>,
+    default_material: &DefaultMaterial,
 ) -> Entity {
     // Spawn a PBR entity with the mesh and material of the first GLTF Primitive
-    parent
-        .spawn_bundle(PbrBundle {
-            mesh: primitive.0.mesh.clone(),
-            // (unwrap: material is optional, we assume this primitive has one)
-            material: primitive.0.material.clone().unwrap(),
-            ..Default::default()
-        })
+    let mut entity = parent.spawn_bundle(PbrBundle {
+        mesh: primitive.0.mesh.clone(),
+        material: primitive
+            .0
+            .material
+            .clone()
+            .unwrap_or_else(|| default_material.0.clone()),
+        ..Default::default()
+    });
+    entity
         .insert_bundle(PickableBundle::default())
         .insert(PrimitiveIdentifier {
             mesh_index,
             primitive_index: primitive.1.index(),
-        })
-        .id()
+        });
+    if primitive.0.material.is_none() {
+        entity.insert(FallbackMaterial);
+    }
+    if let Some(deformation) = deformation {
+        entity.insert(deformation);
+    }
+    entity.id()
 }
@@ -0,0 +1,371 @@
+/// This module saves the exploded view of the current scene as a new file, so
+/// other viewers can show the same arrangement
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde_json::{json, Value};
+
+use crate::{
+    convert::{write_source, Format, Source},
+    explosion::ExplodedPart,
+    hierarchy::NodeIndex,
+    ui::{right_section, RightPanel, StatusPanel},
+    InspectorState,
+};
+
+pub struct ExportPlugin;
+
+impl Plugin for ExportPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_startup_system_to_stage(StartupStage::PostStartup, spawn_export_panel)
+            .add_system(export_buttons);
+    }
+}
+
+/// How a node is moved by the explosion
+pub struct ExplodedNode {
+    pub node: usize,
+    /// The transform of the node as defined in the file
+    pub original: Transform,
+    pub offset: Vec3,
+}
+
+/// The length of the animation from the assembled to the exploded arrangement in seconds
+const ANIMATION_DURATION: f32 = 1.0;
+
+/// Writes the file at `input` as a GLB with the nodes moved by their explosion
+/// offsets. With `animated`, an animation moves them from their original
+/// translation to the exploded one.
+pub fn export_exploded(
+    input: &Path,
+    output: &Path,
+    nodes: &[ExplodedNode],
+    animated: bool,
+) -> Result<Vec<PathBuf>, String> {
+    let mut source = Source::read(input)?;
+    let moved: Vec<&ExplodedNode> = nodes
+        .iter()
+        .filter(|node| node.offset != Vec3::ZERO)
+        .collect();
+    for exploded in &moved {
+        // The file is read again and may have changed since it was opened
+        let node = match source
+            .root
+            .get_mut("nodes")
+            .and_then(|nodes| nodes.get_mut(exploded.node))
+            .and_then(Value::as_object_mut)
+        {
+            Some(it) => it,
+            None => return Err(format!("Node {} does not exist", exploded.node)),
+        };
+        // Animations can only target nodes with separate transform properties
+        if node.remove("matrix").is_some() {
+            node.insert(
+                "rotation".to_string(),
+                json!(Vec4::from(exploded.original.rotation).to_array()),
+            );
+            node.insert(
+                "scale".to_string(),
+                json!(exploded.original.scale.to_array()),
+            );
+        }
+        node.insert(
+            "translation".to_string(),
+            json!((exploded.original.translation + exploded.offset).to_array()),
+        );
+    }
+    if animated && !moved.is_empty() {
+        add_animation(&mut source, &moved);
+    }
+    write_source(source, output, Format::Glb)
+}
+
+/// Adds an animation with two keyframes per moved node, the original and the exploded translation
+fn add_animation(source: &mut Source, moved: &[&ExplodedNode]) {
+    let mut data: Vec<u8> = vec![];
+    for time in [0.0, ANIMATION_DURATION] {
+        data.extend(f32::to_le_bytes(time));
+    }
+    for exploded in moved {
+        let translation = exploded.original.translation;
+        for value in [translation, translation + exploded.offset] {
+            for component in value.to_array() {
+                data.extend(component.to_le_bytes());
+            }
+        }
+    }
+    let length = data.len();
+    let buffer = source.add_buffer(data);
+    let view = source.add(
+        "bufferViews",
+        json!({ "buffer": buffer, "byteLength": length }),
+    );
+    let times = source.add(
+        "accessors",
+        json!({
+            "bufferView": view,
+            "componentType": 5126,
+            "count": 2,
+            "type": "SCALAR",
+            "min": [0.0],
+            "max": [ANIMATION_DURATION],
+        }),
+    );
+    let mut channels = vec![];
+    let mut samplers = vec![];
+    for (index, exploded) in moved.iter().enumerate() {
+        let (start, end) = (
+            exploded.original.translation,
+            exploded.original.translation + exploded.offset,
+        );
+        let translations = source.add(
+            "accessors",
+            json!({
+                "bufferView": view,
+                "byteOffset": 8 + index * 24,
+                "componentType": 5126,
+                "count": 2,
+                "type": "VEC3",
+                "min": start.min(end).to_array(),
+                "max": start.max(end).to_array(),
+            }),
+        );
+        channels.push(json!({
+            "sampler": index,
+            "target": { "node": exploded.node, "path": "translation" },
+        }));
+        samplers.push(json!({
+            "input": times,
+            "output": translations,
+            "interpolation": "LINEAR",
+        }));
+    }
+    source.add(
+        "animations",
+        json!({ "name": "Explode", "channels": channels, "samplers": samplers }),
+    );
+}
+
+/// The file the exploded view of `input` is written to, next to it
+fn output_path(input: &Path) -> PathBuf {
+    let stem = input
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    input.with_file_name(format!("{}_exploded.glb", stem))
+}
+
+/// Exports the exploded view, with an animation if true
+#[derive(Component, Clone, Copy)]
+struct ExportButton(bool);
+
+fn spawn_export_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    right_panel_query: Query<Entity, With<RightPanel>>,
+) {
+    let text_style = TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 16.0,
+        color: Color::WHITE,
+    };
+    let panel = commands
+        .spawn_bundle(right_section())
+        .with_children(|parent| {
+            parent
+                .spawn_bundle(NodeBundle {
+                    style: Style {
+                        flex_direction: FlexDirection::Row,
+                        ..Default::default()
+                    },
+                    color: Color::NONE.into(),
+                    ..Default::default()
+                })
+                .with_children(|parent| {
+                    parent.spawn_bundle(TextBundle {
+                        text: Text::with_section(
+                            "Export exploded view: ",
+                            text_style.clone(),
+                            Default::default(),
+                        ),
+                        ..Default::default()
+                    });
+                    for (label, animated) in [("[Static] ", false), ("[Animated]", true)] {
+                        parent
+                            .spawn_bundle(TextBundle {
+                                text: Text::with_section(
+                                    label,
+                                    text_style.clone(),
+                                    Default::default(),
+                                ),
+                                ..Default::default()
+                            })
+                            .insert(Interaction::default())
+                            .insert(ExportButton(animated));
+                    }
+                });
+        })
+        .id();
+    commands.entity(right_panel_query.single()).add_child(panel);
+}
+
+fn export_buttons(
+    interaction_query: Query<(&Interaction, &ExportButton), Changed<Interaction>>,
+    state: Res<InspectorState>,
+    part_query: Query<(&NodeIndex, &ExplodedPart)>,
+    mut status_query: Query<&mut Text, With<StatusPanel>>,
+) {
+    let file = match state.current_file {
+        Some(it) => it,
+        _ => return,
+    };
+    for (_, button) in interaction_query
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Clicked)
+    {
+        let nodes: Vec<ExplodedNode> = part_query
+            .iter()
+            .map(|(index, part)| ExplodedNode {
+                node: index.0,
+                original: part.original,
+                offset: part.offset(&state),
+            })
+            .collect();
+        let input = Path::new("assets").join(&file.path);
+        let output = output_path(&input);
+        let status = match export_exploded(&input, &output, &nodes, button.0) {
+            Ok(_) => {
+                info!("Exported the exploded view to {}", output.display());
+                format!(
+                    "Wrote {} at explosion factor {:.1}",
+                    output.display(),
+                    state.explosion_factor
+                )
+            }
+            Err(err) => {
+                error!("Could not export {}: {}", input.display(), err);
+                format!("Could not export: {}", err)
+            }
+        };
+        status_query.single_mut().sections[0].value = status;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::fs;
+
+    use super::*;
+    use crate::{
+        animation::read_animation,
+        document::LoadedDocument,
+        validation::{validate, Severity},
+    };
+
+    /// Writes a file with the given nodes and no buffers
+    fn input_file(name: &str, nodes: Value) -> PathBuf {
+        let directory = std::env::temp_dir().join("gltf_inspector_export");
+        fs::create_dir_all(&directory).unwrap();
+        let path = directory.join(name);
+        let json = json!({ "asset": { "version": "2.0" }, "nodes": nodes });
+        fs::write(&path, json.to_string()).unwrap();
+        path
+    }
+
+    fn exploded(node: usize, translation: Vec3, offset: Vec3) -> ExplodedNode {
+        ExplodedNode {
+            node,
+            original: Transform::from_translation(translation),
+            offset,
+        }
+    }
+
+    #[test]
+    fn moves_nodes_and_animates_them() {
+        let input = input_file("moved.gltf", json!([{ "name": "a" }, {}, {}]));
+        let output = input.with_file_name("moved_exploded.glb");
+        let nodes = [
+            exploded(0, Vec3::ZERO, Vec3::new(1.0, 2.0, 3.0)),
+            exploded(1, Vec3::ONE, Vec3::ZERO),
+            exploded(2, Vec3::X, Vec3::new(0.0, -1.0, 0.0)),
+        ];
+        export_exploded(&input, &output, &nodes, true).unwrap();
+
+        let loaded = LoadedDocument::import(&output).unwrap();
+        let gltf = loaded.document.as_ref().unwrap();
+        let translations: Vec<[f32; 3]> = gltf
+            .nodes()
+            .map(|node| node.transform().decomposed().0)
+            .collect();
+        // The node without offset keeps the properties of the file
+        assert_eq!(
+            translations,
+            [[1.0, 2.0, 3.0], [0.0, 0.0, 0.0], [1.0, -1.0, 0.0]]
+        );
+        assert!(validate(&loaded)
+            .iter()
+            .all(|issue| issue.severity != Severity::Error));
+
+        let animation = gltf.animations().next().unwrap();
+        assert_eq!(animation.name(), Some("Explode"));
+        let channels: Vec<_> = animation.channels().collect();
+        assert_eq!(channels.len(), 2);
+        let clip = read_animation(animation, &loaded);
+        assert_eq!(clip.duration, ANIMATION_DURATION);
+        assert_eq!(
+            clip.channels
+                .iter()
+                .map(|channel| channel.node.0)
+                .collect::<Vec<_>>(),
+            [0, 2]
+        );
+        let exploded_translations = channels[1].sampler().output();
+        assert_eq!(exploded_translations.min(), Some(json!([1.0, -1.0, 0.0])));
+        assert_eq!(exploded_translations.max(), Some(json!([1.0, 0.0, 0.0])));
+    }
+
+    #[test]
+    fn replaces_matrices_by_separate_properties() {
+        let matrix = Mat4::from_scale_rotation_translation(
+            Vec3::splat(2.0),
+            Quat::from_rotation_y(1.0),
+            Vec3::Z,
+        );
+        let input = input_file("matrix.gltf", json!([{ "matrix": matrix.to_cols_array() }]));
+        let output = input.with_file_name("matrix_exploded.glb");
+        let (scale, rotation, translation) = matrix.to_scale_rotation_translation();
+        let nodes = [ExplodedNode {
+            node: 0,
+            original: Transform {
+                translation,
+                rotation,
+                scale,
+            },
+            offset: Vec3::X,
+        }];
+        export_exploded(&input, &output, &nodes, false).unwrap();
+
+        let loaded = LoadedDocument::import(&output).unwrap();
+        let gltf = loaded.document.as_ref().unwrap();
+        assert_eq!(gltf.animations().count(), 0);
+        let node = gltf.nodes().next().unwrap();
+        assert!(matches!(
+            node.transform(),
+            gltf::scene::Transform::Decomposed { .. }
+        ));
+        let (exported, _, exported_scale) = node.transform().decomposed();
+        assert!(Vec3::from(exported).abs_diff_eq(Vec3::new(1.0, 0.0, 1.0), 1e-5));
+        assert!(Vec3::from(exported_scale).abs_diff_eq(Vec3::splat(2.0), 1e-5));
+    }
+
+    #[test]
+    fn fails_for_missing_nodes() {
+        let input = input_file("missing.gltf", json!([{}]));
+        let output = input.with_file_name("missing_exploded.glb");
+        let nodes = [exploded(3, Vec3::ZERO, Vec3::X)];
+        assert_eq!(
+            export_exploded(&input, &output, &nodes, false),
+            Err("Node 3 does not exist".to_string())
+        );
+    }
+}
@@ -1,14 +1,22 @@
 /// This module implements the systems for switching between files
-use crate::InspectorState;
+use std::path::{Path, PathBuf};
+
+use crate::{
+    document::{DocumentLoading, LoadedDocument},
+    utils::{default_scene_index, scene_bounds, scene_roots},
+    validation::hierarchy_issues,
+    InspectorState,
+};
 use bevy::prelude::*;
 use lazy_static::lazy_static;
 
 pub struct File {
-    pub path: &'static str,
+    pub path: String,
     pub camera_transform: Transform,
-    pub scene_index: usize,
+    /// The scene shown when the file is opened, the default scene of the file if None
+    pub scene_index: Option<usize>,
     pub explosion_scale: Vec3,
-    pub name: &'static str,
+    pub name: String,
 }
 
 impl PartialEq for File {
@@ -19,56 +27,154 @@ impl PartialEq for File {
 impl Eq for File {}
 
 lazy_static! {
-    pub static ref FILES: [File; 6] = [
+    /// The files passed on the command line, or the bundled models if there are none
+    pub static ref FILES: Vec<File> = files_from_args(std::env::args().skip(1));
+}
+
+/// The bundled models with hand-tuned camera transforms and explosion scales
+fn bundled_files() -> Vec<File> {
+    vec![
         File {
-            path: "models/FlightHelmet/FlightHelmet.gltf",
-            name: "Flight Helmet",
+            path: "models/FlightHelmet/FlightHelmet.gltf".to_string(),
+            name: "Flight Helmet".to_string(),
             camera_transform: Transform::from_translation(Vec3::new(-0.07, 1.02, 2.40))
                 .looking_at(Vec3::ZERO, Vec3::Y),
-            scene_index: 0,
+            scene_index: None,
             explosion_scale: Vec3::new(1.0, 1.0, 1.0),
         },
         File {
-            path: "models/ammo_collection/scene.gltf",
-            name: "Ammo",
-            camera_transform: Transform::from_translation(Vec3::new(1.04, 0.59, 0.12,))
+            path: "models/ammo_collection/scene.gltf".to_string(),
+            name: "Ammo".to_string(),
+            camera_transform: Transform::from_translation(Vec3::new(1.04, 0.59, 0.12))
                 .looking_at(Vec3::ZERO, Vec3::Y),
-            scene_index: 0,
+            scene_index: None,
             explosion_scale: Vec3::new(-10.0, 10.0, -10.0),
         },
         File {
-            path: "models/steampunk_underwater_explorer/scene.gltf",
-            name: "Steampunk Underwater Explorer",
+            path: "models/steampunk_underwater_explorer/scene.gltf".to_string(),
+            name: "Steampunk Underwater Explorer".to_string(),
             camera_transform: Transform::from_translation(Vec3::new(-12.91, 6.06, -9.04))
                 .looking_at(Vec3::ZERO, Vec3::Y),
-            scene_index: 0,
+            scene_index: None,
             explosion_scale: Vec3::new(-10.0, -10.0, 10.0),
         },
         File {
-            path: "models/Wraith/wraith.gltf",
-            name: "Wraith",
+            path: "models/Wraith/wraith.gltf".to_string(),
+            name: "Wraith".to_string(),
             camera_transform: Transform::from_translation(Vec3::new(-1.84, 70.99, 86.87))
                 .looking_at(Vec3::new(0.0, 40.0, 0.0), Vec3::Y),
-            scene_index: 0,
+            scene_index: None,
             explosion_scale: Vec3::new(2.0, 2.0, 2.0),
         },
         File {
-            path: "models/StarWars/scene.gltf",
-            name: "ATM6 Walker",
+            path: "models/StarWars/scene.gltf".to_string(),
+            name: "ATM6 Walker".to_string(),
             camera_transform: Transform::from_translation(Vec3::new(33.58, 39.04, 63.81))
                 .looking_at(Vec3::new(0.0, 20.0, 0.0), Vec3::Y),
-            scene_index: 0,
+            scene_index: None,
             explosion_scale: Vec3::new(20.0, 20.0, 20.0),
         },
         File {
-            path: "models/ToyCar/glTF/ToyCar.gltf",
-            name: "Toy Car",
+            path: "models/ToyCar/glTF/ToyCar.gltf".to_string(),
+            name: "Toy Car".to_string(),
             camera_transform: Transform::from_translation(Vec3::new(0.09, 0.07, 0.12))
                 .looking_at(Vec3::ZERO, Vec3::Y),
-            scene_index: 0,
+            scene_index: None,
             explosion_scale: Vec3::new(0.2, 0.2, -0.2),
+        },
+    ]
+}
+
+/// Builds the file list from the command line arguments. Directories are
+/// searched recursively for .gltf and .glb files. Files matching a bundled
+/// model keep its preset, all others get a camera and explosion scale derived
+/// from their bounding box.
+fn files_from_args(args: impl Iterator<Item = String>) -> Vec<File> {
+    let mut paths = vec![];
+    for arg in args {
+        collect_gltf_paths(Path::new(&arg), &mut paths);
+    }
+    if paths.is_empty() {
+        return bundled_files();
+    }
+
+    let mut presets = bundled_files();
+    paths
+        .into_iter()
+        .map(|path| {
+            // Missing files are kept with an absolute path, so the load error names them
+            let path = match path.canonicalize() {
+                Ok(path) => path,
+                Err(_) => std::env::current_dir()
+                    .map(|dir| dir.join(&path))
+                    .unwrap_or(path),
+            };
+            match presets.iter().position(|preset| {
+                Path::new("assets").join(&preset.path).canonicalize().ok() == Some(path.clone())
+            }) {
+                Some(index) => presets.remove(index),
+                None => file_from_path(&path),
+            }
+        })
+        .collect()
+}
+
+/// Adds the path if it is a glTF file, or every glTF file below it if it is a directory
+fn collect_gltf_paths(path: &Path, paths: &mut Vec<PathBuf>) {
+    if path.is_dir() {
+        let mut entries: Vec<PathBuf> = match path.read_dir() {
+            Ok(entries) => entries
+                .filter_map(|entry| entry.ok())
+                .map(|e| e.path())
+                .collect(),
+            Err(err) => {
+                eprintln!("Could not read directory {}: {}", path.display(), err);
+                return;
+            }
+        };
+        entries.sort();
+        for entry in entries {
+            collect_gltf_paths(&entry, paths);
         }
-    ];
+    } else if is_gltf_path(path) {
+        paths.push(path.to_path_buf());
+    } else if !path.exists() {
+        eprintln!("No such file or directory: {}", path.display());
+    }
+}
+
+fn is_gltf_path(path: &Path) -> bool {
+    path.extension()
+        .and_then(|ext| ext.to_str())
+        .map(|ext| ext.eq_ignore_ascii_case("gltf") || ext.eq_ignore_ascii_case("glb"))
+        .unwrap_or(false)
+}
+
+/// Creates a file entry without a preset, framing the camera around the
+/// bounds of the default scene and scaling the explosion to the model size.
+/// Files that can't be opened are kept, so their error is shown when they are opened.
+fn file_from_path(path: &Path) -> File {
+    let gltf = gltf::Gltf::open(path)
+        .ok()
+        .filter(|gltf| hierarchy_issues(gltf).is_empty());
+    let (center, radius) = gltf
+        .as_ref()
+        .and_then(|gltf| scene_bounds(scene_roots(gltf, default_scene_index(gltf))))
+        .map(|bounds| (bounds.center(), bounds.radius()))
+        .unwrap_or((Vec3::ZERO, 1.0));
+    let camera_direction = Vec3::new(1.0, 0.6, 1.0).normalize();
+
+    File {
+        path: path.to_string_lossy().into_owned(),
+        name: path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        camera_transform: Transform::from_translation(center + camera_direction * radius * 2.5)
+            .looking_at(center, Vec3::Y),
+        scene_index: None,
+        explosion_scale: Vec3::splat(radius * 0.1),
+    }
 }
 
 pub struct FilePickerPlugin;
@@ -76,15 +182,25 @@ pub struct FilePickerPlugin;
 impl Plugin for FilePickerPlugin {
     fn build(&self, app: &mut App) {
         app.add_event::<FileChangedEvent>()
+            .init_resource::<Navigation>()
             .add_startup_system(intial_file)
-            .add_system(switch_between_files);
+            .add_system(switch_between_files.before(DocumentLoading))
+            .add_system(skip_unreadable_file.after(DocumentLoading));
     }
 }
 
 pub struct FileChangedEvent;
 
+/// The direction the user last switched files in, unreadable files are skipped in it
+#[derive(Default)]
+enum Navigation {
+    #[default]
+    Forward,
+    Backward,
+}
+
 fn intial_file(mut state: ResMut<InspectorState>, mut writer: EventWriter<FileChangedEvent>) {
-    state.current_file = Some(&FILES[0]);
+    state.current_file = FILES.first();
     writer.send(FileChangedEvent);
 }
 
@@ -107,6 +223,7 @@ fn prev_file(current: &'static File) -> &'static File {
 fn switch_between_files(
     mut state: ResMut<InspectorState>,
     keyboard_input: Res<Input<KeyCode>>,
+    mut navigation: ResMut<Navigation>,
     mut writer: EventWriter<FileChangedEvent>,
 ) {
     if !keyboard_input.is_changed() {
@@ -115,9 +232,38 @@ fn switch_between_files(
 
     if keyboard_input.just_pressed(KeyCode::Left) {
         state.current_file = state.current_file.map(prev_file);
+        *navigation = Navigation::Backward;
         writer.send(FileChangedEvent);
     } else if keyboard_input.just_pressed(KeyCode::Right) {
         state.current_file = state.current_file.map(next_file);
+        *navigation = Navigation::Forward;
         writer.send(FileChangedEvent);
     }
 }
+
+/// Opens the next file in the direction of the last switch when the current one
+/// could not be loaded, until every file has been tried once
+fn skip_unreadable_file(
+    loaded: Res<LoadedDocument>,
+    navigation: Res<Navigation>,
+    mut state: ResMut<InspectorState>,
+    mut writer: EventWriter<FileChangedEvent>,
+    mut skipped: Local<usize>,
+) {
+    if !loaded.is_changed() {
+        return;
+    }
+    if loaded.document.is_some() {
+        *skipped = 0;
+        return;
+    }
+    if *skipped + 1 >= FILES.len() {
+        return;
+    }
+    *skipped += 1;
+    state.current_file = state.current_file.map(match *navigation {
+        Navigation::Forward => next_file,
+        Navigation::Backward => prev_file,
+    });
+    writer.send(FileChangedEvent);
+}
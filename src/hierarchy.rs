@@ -1,17 +1,27 @@
+use std::collections::HashSet;
+
 use bevy::{
-    input::mouse::{MouseScrollUnit, MouseWheel},
+    input::{
+        mouse::{MouseScrollUnit, MouseWheel},
+        InputSystem,
+    },
     prelude::*,
+    window::ReceivedCharacter,
 };
+use gltf::Document;
 
 use crate::{
-    file_picker::FileChangedEvent,
+    document::LoadedDocument,
+    scene_picker::{SceneChangedEvent, SceneSelection},
+    selection::SelectNodesEvent,
     ui::LeftPanel,
-    utils::{get_current_scene, get_gltf},
+    utils::{get_current_scene, get_gltf, matches_filter, morph_target_names},
     InspectorState,
 };
 
+/// A list inside a panel with hidden overflow, moved by scrolling over the panel
 #[derive(Component, Default)]
-struct ScrollingList {
+pub struct ScrollingList {
     pub position: f32,
 }
 
@@ -19,20 +29,30 @@ struct ScrollingList {
 struct ScrollingListPanel;
 
 /// This plugin prints the structure of the opened glTF file into a scrollable
-/// list, showing the tree of nodes and the meshes attached to the individual nodes
+/// tree, showing the nodes and the meshes attached to the individual nodes.
+/// Nodes can be expanded and collapsed to show or hide their children, and the
+/// tree can be filtered by node, mesh and material names.
 pub struct HierarchyPlugin;
 
 impl Plugin for HierarchyPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(remove_existing_list)
-            .add_system(print_structure)
+        app.init_resource::<TreeState>()
+            .add_startup_system_to_stage(StartupStage::PostStartup, spawn_filter_field)
+            // Runs before all other systems, so typed keys don't trigger their shortcuts
+            .add_system_to_stage(CoreStage::PreUpdate, edit_filter.after(InputSystem))
+            .add_system(update_filter_field)
+            .add_system(remove_existing_list)
+            .add_system(print_structure.after(SceneSelection))
+            .add_system(toggle_tree_nodes)
+            .add_system(tree_controls)
+            .add_system(update_tree_rows)
             .add_system(mouse_scroll);
     }
 }
 
-/// Finds and removes the existing List UI element if the file has changed
+/// Finds and removes the existing List UI element if the scene has changed
 fn remove_existing_list(
-    mut reader: EventReader<FileChangedEvent>,
+    mut reader: EventReader<SceneChangedEvent>,
     mut commands: Commands,
     query: Query<Entity, With<ScrollingListPanel>>,
 ) {
@@ -44,10 +64,12 @@ fn remove_existing_list(
     }
 }
 
-// Prints the structure of the loaded GLTF file as
+// Prints the structure of the loaded GLTF file as a tree
 fn print_structure(
-    mut reader: EventReader<FileChangedEvent>,
+    mut reader: EventReader<SceneChangedEvent>,
     state: Res<InspectorState>,
+    loaded: Res<LoadedDocument>,
+    mut tree_state: ResMut<TreeState>,
     mut commands: Commands,
     asset_server: Res<AssetServer>,
     mut left_panel_query: Query<Entity, With<LeftPanel>>,
@@ -56,10 +78,21 @@ fn print_structure(
         return;
     }
 
-    let gltf = match get_gltf(&state) {
+    let gltf = match get_gltf(&loaded) {
         Some(it) => it,
         _ => return,
     };
+    let roots = get_current_scene(&state, gltf);
+    let title = match state
+        .current_scene
+        .and_then(|index| gltf.scenes().nth(index))
+    {
+        Some(scene) => format!("Scene {}{}", scene.index(), name_suffix(scene.name())),
+        None => "Root nodes".to_string(),
+    };
+    // Only the root nodes and their direct children are shown at first
+    tree_state.expanded = roots.iter().map(|node| NodeIndex(node.index())).collect();
+    apply_filter(&mut tree_state, gltf, &state);
 
     // List based on https://bevyengine.org/examples/ui/ui/ last accessed: 2022-02-06
     // List with hidden overflow
@@ -94,48 +127,23 @@ fn print_structure(
                 })
                 .insert(ScrollingList::default())
                 .with_children(|parent| {
-                    for root_node in get_current_scene(&state, &gltf)
-                        .nodes()
-                        .filter(|node| node.camera().is_none())
-                    {
-                        let style = Style {
-                            flex_shrink: 0.,
-                            size: Size::new(Val::Undefined, Val::Px(20.)),
-                            margin: Rect {
-                                left: Val::Percent(0.0),
-                                right: Val::Auto,
-                                ..Default::default()
-                            },
-                            ..Default::default()
-                        };
-                        let text_style = TextStyle {
-                            font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-                            font_size: 20.,
-                            color: Color::WHITE,
-                        };
-                        let text_alignment = TextAlignment {
-                            vertical: VerticalAlign::Center,
-                            horizontal: HorizontalAlign::Left,
-                        };
-
-                        parent
-                            .spawn_bundle(TextBundle {
-                                style: style.clone(),
-                                text: Text::with_section(
-                                    format!(
-                                        "Scene {} references node {}",
-                                        state.current_file.unwrap().scene_index,
-                                        root_node.index(),
-                                    ),
-                                    text_style.clone(),
-                                    text_alignment,
-                                ),
-                                ..Default::default()
-                            })
-                            .insert(NodeIndex(root_node.index()));
-
-                        // List items
-                        traverse_gltf(parent, root_node, &asset_server)
+                    spawn_tree_controls(parent, &asset_server);
+                    parent.spawn_bundle(TextBundle {
+                        style: row_style(0),
+                        text: Text::with_section(
+                            title,
+                            entry_text_style(&asset_server),
+                            entry_text_alignment(),
+                        ),
+                        ..Default::default()
+                    });
+                    let mut entries = vec![];
+                    for root_node in roots {
+                        traverse_gltf(root_node, &mut vec![], &mut entries);
+                    }
+                    // List items
+                    for entry in entries {
+                        spawn_entry(parent, entry, &asset_server);
                     }
                 });
         })
@@ -146,13 +154,27 @@ fn print_structure(
 
 /// Since every node is only part of the scene tree in exactly one place,
 /// knowing this place allows us to locate it within in a scene
-#[derive(Clone, Copy, Debug, Component, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Component, PartialEq, Eq, Hash)]
 pub struct NodeIndex(pub usize);
 
 /// Meshes can be referenced from multiple nodes
 #[derive(Clone, Copy, Debug, Component, PartialEq, Eq)]
 pub struct MeshIndex(pub usize);
 
+/// Makes an entry in the list clickable, selecting the primitives of the
+/// node in the scene
+#[derive(Clone, Copy, Debug, Component)]
+pub struct HierarchyButton {
+    pub node: NodeIndex,
+    /// Whether the primitives of the children of the node are selected as well
+    pub include_children: bool,
+}
+
+/// Makes the entry of a camera clickable, viewing the scene through the
+/// camera of the node
+#[derive(Clone, Copy, Debug, Component)]
+pub struct ViewpointButton(pub NodeIndex);
+
 /// As a mesh can be made up of multiple primitives
 /// this struct captures both the MeshIndex and the
 /// index of the primitive
@@ -162,85 +184,602 @@ pub struct PrimitiveIdentifier {
     pub primitive_index: usize,
 }
 
-/// Tranverses a [gtlf::Gltf] file and generates a list of elements
-fn traverse_gltf(
-    parent: &mut ChildBuilder,
-    parent_node: gltf::Node,
-    asset_server: &Res<AssetServer>,
-) {
-    let style = Style {
+#[derive(Default)]
+pub struct TreeState {
+    /// The nodes whose children are shown in the tree
+    pub expanded: HashSet<NodeIndex>,
+    /// The text entered into the filter field
+    pub filter: String,
+    /// Whether keys are currently typed into the filter field
+    pub editing_filter: bool,
+    /// The nodes matching the filter
+    pub matches: Vec<NodeIndex>,
+    /// The matching nodes and their ancestors, None if nothing is filtered
+    pub visible: Option<HashSet<NodeIndex>>,
+}
+
+/// A row of the tree, which is only shown while all its ancestors are expanded
+/// and its node is not filtered out
+#[derive(Component)]
+struct TreeRow {
+    node: NodeIndex,
+    ancestors: Vec<NodeIndex>,
+}
+
+#[derive(Component)]
+struct FilterField;
+
+/// The expand/collapse button in front of a node that has children or a mesh
+#[derive(Component)]
+struct TreeToggle(NodeIndex);
+
+#[derive(Component, Clone, Copy)]
+enum TreeControl {
+    ExpandAll,
+    CollapseAll,
+}
+
+fn entry_text_style(asset_server: &Res<AssetServer>) -> TextStyle {
+    TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 20.,
+        color: Color::WHITE,
+    }
+}
+
+fn entry_text_alignment() -> TextAlignment {
+    TextAlignment {
+        vertical: VerticalAlign::Center,
+        horizontal: HorizontalAlign::Left,
+    }
+}
+
+/// A row of the list, indented by the depth in the tree
+fn row_style(depth: usize) -> Style {
+    Style {
         flex_shrink: 0.,
         size: Size::new(Val::Undefined, Val::Px(20.)),
         margin: Rect {
-            left: Val::Percent(0.0),
+            left: Val::Px(depth as f32 * 16.0),
             right: Val::Auto,
             ..Default::default()
         },
         ..Default::default()
-    };
-    let text_style = TextStyle {
-        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
-        font_size: 20.,
-        color: Color::WHITE,
-    };
-    let text_alignment = TextAlignment {
-        vertical: VerticalAlign::Center,
-        horizontal: HorizontalAlign::Left,
-    };
-    if let Some(mesh) = parent_node.mesh() {
-        parent
-            .spawn_bundle(TextBundle {
-                style: style.clone(),
+    }
+}
+
+fn name_suffix(name: Option<&str>) -> String {
+    name.map(|name| format!(" {}", name)).unwrap_or_default()
+}
+
+fn spawn_tree_controls(parent: &mut ChildBuilder, asset_server: &Res<AssetServer>) {
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                ..row_style(0)
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .with_children(|parent| {
+            for (label, control) in [
+                ("[Expand all] ", TreeControl::ExpandAll),
+                ("[Collapse all]", TreeControl::CollapseAll),
+            ] {
+                parent
+                    .spawn_bundle(TextBundle {
+                        text: Text::with_section(
+                            label,
+                            entry_text_style(asset_server),
+                            entry_text_alignment(),
+                        ),
+                        ..Default::default()
+                    })
+                    .insert(Interaction::default())
+                    .insert(control);
+            }
+        });
+}
+
+/// Spawns a row consisting of the expand/collapse toggle and the entry itself,
+/// which is tagged with `tags`
+fn spawn_row(
+    parent: &mut ChildBuilder,
+    node: NodeIndex,
+    ancestors: &[NodeIndex],
+    toggle: Option<NodeIndex>,
+    label: String,
+    tags: impl Bundle,
+    asset_server: &Res<AssetServer>,
+) {
+    parent
+        .spawn_bundle(NodeBundle {
+            style: Style {
+                flex_direction: FlexDirection::Row,
+                ..row_style(ancestors.len())
+            },
+            color: Color::NONE.into(),
+            ..Default::default()
+        })
+        .insert(TreeRow {
+            node,
+            ancestors: ancestors.to_vec(),
+        })
+        .with_children(|parent| {
+            let mut toggle_entity = parent.spawn_bundle(TextBundle {
+                style: Style {
+                    size: Size::new(Val::Px(16.), Val::Px(20.)),
+                    ..Default::default()
+                },
                 text: Text::with_section(
-                    format!(
-                        "Node {} references mesh {}",
-                        parent_node.index(),
-                        mesh.index(),
-                    ),
-                    text_style.clone(),
-                    text_alignment,
+                    "",
+                    entry_text_style(asset_server),
+                    entry_text_alignment(),
                 ),
                 ..Default::default()
-            })
-            .insert(MeshIndex(mesh.index()));
+            });
+            if let Some(node) = toggle {
+                toggle_entity
+                    .insert(Interaction::default())
+                    .insert(TreeToggle(node));
+            }
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(
+                        label,
+                        entry_text_style(asset_server),
+                        entry_text_alignment(),
+                    ),
+                    ..Default::default()
+                })
+                .insert(Interaction::default())
+                .insert_bundle(tags);
+        });
+}
+
+/// What an entry of the tree represents
+#[derive(Clone, Copy, Debug)]
+pub enum TreeEntryKind {
+    Node,
+    Mesh(MeshIndex),
+    MorphTarget,
+    Skin,
+    /// A joint of the skin above, which is the given node
+    Joint(NodeIndex),
+    Camera,
+}
+
+/// An entry of the tree, shown as a row in the hierarchy and printed by the
+/// headless `inspect` command
+#[derive(Clone, Debug)]
+pub struct TreeEntry {
+    /// The node the entry belongs to
+    pub node: NodeIndex,
+    /// The path of nodes above the entry, its length being the depth of the entry
+    pub ancestors: Vec<NodeIndex>,
+    pub kind: TreeEntryKind,
+    pub label: String,
+    /// Whether the entry is a node with entries below it
+    pub expandable: bool,
+}
+
+/// Tranverses a [gtlf::Gltf] file and generates an entry for every node, mesh,
+/// skin and camera, `ancestors` being the path from the root of the scene to `node`
+pub fn traverse_gltf(
+    node: gltf::Node,
+    ancestors: &mut Vec<NodeIndex>,
+    entries: &mut Vec<TreeEntry>,
+) {
+    let index = NodeIndex(node.index());
+    let entry = |ancestors: &[NodeIndex], kind, label| TreeEntry {
+        node: index,
+        ancestors: ancestors.to_vec(),
+        kind,
+        label,
+        expandable: false,
+    };
+    entries.push(TreeEntry {
+        expandable: node.mesh().is_some()
+            || node.camera().is_some()
+            || node.skin().is_some()
+            || node.children().next().is_some(),
+        ..entry(
+            ancestors,
+            TreeEntryKind::Node,
+            format!("Node {}{}", node.index(), name_suffix(node.name())),
+        )
+    });
+
+    ancestors.push(index);
+    if let Some(mesh) = node.mesh() {
+        entries.push(entry(
+            ancestors,
+            TreeEntryKind::Mesh(MeshIndex(mesh.index())),
+            format!("Mesh {}{}", mesh.index(), name_suffix(mesh.name())),
+        ));
+        // The morph targets are indented below the mesh
+        let weights = node
+            .weights()
+            .or_else(|| mesh.weights())
+            .unwrap_or_default();
+        let target_ancestors = [ancestors.as_slice(), &[index]].concat();
+        for (target, name) in morph_target_names(&mesh).into_iter().enumerate() {
+            entries.push(entry(
+                &target_ancestors,
+                TreeEntryKind::MorphTarget,
+                format!(
+                    "Morph target {} {} ({})",
+                    target,
+                    name,
+                    weights.get(target).copied().unwrap_or_default()
+                ),
+            ));
+        }
+    }
+    if let Some(skin) = node.skin() {
+        entries.push(entry(
+            ancestors,
+            TreeEntryKind::Skin,
+            format!(
+                "Skin {}{} ({} joints)",
+                skin.index(),
+                name_suffix(skin.name()),
+                skin.joints().count()
+            ),
+        ));
+        let joint_ancestors = [ancestors.as_slice(), &[index]].concat();
+        for joint in skin.joints() {
+            entries.push(entry(
+                &joint_ancestors,
+                TreeEntryKind::Joint(NodeIndex(joint.index())),
+                format!("Joint: Node {}{}", joint.index(), name_suffix(joint.name())),
+            ));
+        }
+    }
+    if let Some(camera) = node.camera() {
+        let projection = match camera.projection() {
+            gltf::camera::Projection::Perspective(_) => "perspective",
+            gltf::camera::Projection::Orthographic(_) => "orthographic",
+        };
+        entries.push(entry(
+            ancestors,
+            TreeEntryKind::Camera,
+            format!(
+                "Camera {}{} ({})",
+                camera.index(),
+                name_suffix(camera.name()),
+                projection
+            ),
+        ));
+    }
+    for child in node.children() {
+        traverse_gltf(child, ancestors, entries);
+    }
+    ancestors.pop();
+}
+
+/// Spawns the row of an entry, tagged so that clicking it selects its node,
+/// mesh or joint, or views the scene through its camera
+fn spawn_entry(parent: &mut ChildBuilder, entry: TreeEntry, asset_server: &Res<AssetServer>) {
+    let TreeEntry {
+        node,
+        ancestors,
+        kind,
+        label,
+        expandable,
+    } = entry;
+    let toggle = expandable.then_some(node);
+    match kind {
+        TreeEntryKind::Node => spawn_row(
+            parent,
+            node,
+            &ancestors,
+            toggle,
+            label,
+            (
+                node,
+                HierarchyButton {
+                    node,
+                    include_children: true,
+                },
+            ),
+            asset_server,
+        ),
+        TreeEntryKind::Mesh(mesh) => spawn_row(
+            parent,
+            node,
+            &ancestors,
+            toggle,
+            label,
+            (
+                mesh,
+                HierarchyButton {
+                    node,
+                    include_children: false,
+                },
+            ),
+            asset_server,
+        ),
+        // Clicking a joint selects the primitives attached to the joint node
+        TreeEntryKind::Joint(joint) => spawn_row(
+            parent,
+            node,
+            &ancestors,
+            toggle,
+            label,
+            (HierarchyButton {
+                node: joint,
+                include_children: false,
+            },),
+            asset_server,
+        ),
+        TreeEntryKind::Camera => spawn_row(
+            parent,
+            node,
+            &ancestors,
+            toggle,
+            label,
+            (ViewpointButton(node),),
+            asset_server,
+        ),
+        TreeEntryKind::MorphTarget | TreeEntryKind::Skin => {
+            spawn_row(parent, node, &ancestors, toggle, label, (), asset_server)
+        }
     }
+}
 
-    for node in parent_node
-        .children()
-        .filter(|node| node.camera().is_none())
+/// Expands or collapses a node when its toggle is clicked
+fn toggle_tree_nodes(
+    interaction_query: Query<(&Interaction, &TreeToggle), Changed<Interaction>>,
+    mut tree_state: ResMut<TreeState>,
+) {
+    for (_, toggle) in interaction_query
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Clicked)
     {
-        parent
-            .spawn_bundle(TextBundle {
-                style: style.clone(),
-                text: Text::with_section(
-                    format!(
-                        "Node {} references node {}",
-                        parent_node.index(),
-                        node.index(),
-                    ),
-                    text_style.clone(),
-                    text_alignment,
-                ),
-                ..Default::default()
-            })
-            .insert(NodeIndex(node.index()));
-        traverse_gltf(parent, node, asset_server);
+        if !tree_state.expanded.remove(&toggle.0) {
+            tree_state.expanded.insert(toggle.0);
+        }
+    }
+}
+
+fn tree_controls(
+    interaction_query: Query<(&Interaction, &TreeControl), Changed<Interaction>>,
+    loaded: Res<LoadedDocument>,
+    mut tree_state: ResMut<TreeState>,
+) {
+    for (_, control) in interaction_query
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Clicked)
+    {
+        match control {
+            TreeControl::ExpandAll => {
+                if let Some(gltf) = get_gltf(&loaded) {
+                    tree_state.expanded =
+                        gltf.nodes().map(|node| NodeIndex(node.index())).collect();
+                }
+            }
+            TreeControl::CollapseAll => tree_state.expanded.clear(),
+        }
+    }
+}
+
+/// Hides the rows below collapsed nodes and updates the toggles
+fn update_tree_rows(
+    tree_state: Res<TreeState>,
+    added_rows_query: Query<(), Added<TreeRow>>,
+    mut row_query: Query<(&mut Style, &TreeRow)>,
+    mut toggle_query: Query<(&mut Text, &TreeToggle)>,
+) {
+    // The rows of a new file are spawned after the tree state was reset
+    if !tree_state.is_changed() && added_rows_query.iter().next().is_none() {
+        return;
+    }
+    for (mut style, row) in row_query.iter_mut() {
+        let visible = row
+            .ancestors
+            .iter()
+            .all(|ancestor| tree_state.expanded.contains(ancestor))
+            && tree_state
+                .visible
+                .as_ref()
+                .is_none_or(|visible| visible.contains(&row.node));
+        style.display = if visible {
+            Display::Flex
+        } else {
+            Display::None
+        };
+    }
+    for (mut text, toggle) in toggle_query.iter_mut() {
+        text.sections[0].value = if tree_state.expanded.contains(&toggle.0) {
+            "-".to_string()
+        } else {
+            "+".to_string()
+        };
     }
 }
 
+/// Adds the filter field above the list
+fn spawn_filter_field(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    left_panel_query: Query<Entity, With<LeftPanel>>,
+) {
+    let field = commands
+        .spawn_bundle(TextBundle {
+            style: Style {
+                margin: Rect {
+                    top: Val::Px(10.0),
+                    ..Default::default()
+                },
+                ..row_style(0)
+            },
+            text: Text::with_section("", entry_text_style(&asset_server), entry_text_alignment()),
+            ..Default::default()
+        })
+        .insert(Interaction::default())
+        .insert(FilterField)
+        .id();
+    commands.entity(left_panel_query.single()).add_child(field);
+}
+
+/// Types into the filter field while it is focused, by clicking on it or pressing "/".
+/// Enter selects all matching nodes, Escape stops editing.
+fn edit_filter(
+    mut tree_state: ResMut<TreeState>,
+    mut keyboard_input: ResMut<Input<KeyCode>>,
+    mut characters: EventReader<ReceivedCharacter>,
+    field_query: Query<&Interaction, (Changed<Interaction>, With<FilterField>)>,
+    loaded: Res<LoadedDocument>,
+    state: Res<InspectorState>,
+    mut select_writer: EventWriter<SelectNodesEvent>,
+) {
+    if !tree_state.editing_filter {
+        let clicked = field_query
+            .iter()
+            .any(|interaction| *interaction == Interaction::Clicked);
+        if clicked || keyboard_input.just_pressed(KeyCode::Slash) {
+            tree_state.editing_filter = true;
+            keyboard_input.clear();
+        }
+        // Characters typed before editing started are not part of the filter
+        characters.iter().for_each(drop);
+        return;
+    }
+
+    let mut filter = tree_state.filter.clone();
+    for character in characters.iter().map(|event| event.char) {
+        match character {
+            // Backspace, sent as delete on macOS
+            '\u{8}' | '\u{7f}' => {
+                filter.pop();
+            }
+            '\r' | '\n' => {
+                tree_state.editing_filter = false;
+                if !tree_state.matches.is_empty() {
+                    select_writer.send(SelectNodesEvent {
+                        nodes: tree_state.matches.clone(),
+                        include_children: false,
+                    });
+                }
+            }
+            c if !c.is_control() => filter.push(c),
+            _ => {}
+        }
+    }
+    if keyboard_input.just_pressed(KeyCode::Escape) {
+        tree_state.editing_filter = false;
+    }
+    keyboard_input.clear();
+
+    if filter != tree_state.filter {
+        tree_state.filter = filter;
+        if let Some(gltf) = get_gltf(&loaded) {
+            apply_filter(&mut tree_state, gltf, &state);
+        }
+    }
+}
+
+fn update_filter_field(
+    tree_state: Res<TreeState>,
+    mut field_query: Query<&mut Text, With<FilterField>>,
+) {
+    if !tree_state.is_changed() {
+        return;
+    }
+    let mut text = field_query.single_mut();
+    text.sections[0].value = match (tree_state.editing_filter, tree_state.filter.is_empty()) {
+        (true, _) => format!("Filter: {}|", tree_state.filter),
+        (false, true) => "Filter: press / to search".to_string(),
+        (false, false) => format!(
+            "Filter: {} ({} matches)",
+            tree_state.filter,
+            tree_state.matches.len()
+        ),
+    };
+}
+
+/// Finds the nodes matching the filter and expands their ancestors
+fn apply_filter(tree_state: &mut TreeState, gltf: &Document, state: &Res<InspectorState>) {
+    tree_state.matches.clear();
+    if tree_state.filter.is_empty() {
+        tree_state.visible = None;
+        return;
+    }
+    let mut visible = HashSet::new();
+    let mut matches = vec![];
+    for node in get_current_scene(state, gltf) {
+        filter_nodes(node, &tree_state.filter, &mut visible, &mut matches);
+    }
+    tree_state.expanded.extend(visible.iter().copied());
+    tree_state.visible = Some(visible);
+    tree_state.matches = matches;
+}
+
+/// Adds the node to `visible` if it or one of its descendants matches the
+/// filter, returning whether it was added
+fn filter_nodes(
+    node: gltf::Node,
+    filter: &str,
+    visible: &mut HashSet<NodeIndex>,
+    matches: &mut Vec<NodeIndex>,
+) -> bool {
+    let name_matches = |name: Option<&str>| name.is_some_and(|name| matches_filter(name, filter));
+    let is_match = name_matches(node.name())
+        || node
+            .camera()
+            .is_some_and(|camera| name_matches(camera.name()))
+        || node.mesh().is_some_and(|mesh| {
+            name_matches(mesh.name())
+                || mesh
+                    .primitives()
+                    .any(|primitive| name_matches(primitive.material().name()))
+        });
+    if is_match {
+        matches.push(NodeIndex(node.index()));
+    }
+    let mut any_child = false;
+    for child in node.children() {
+        any_child |= filter_nodes(child, filter, visible, matches);
+    }
+    if is_match || any_child {
+        visible.insert(NodeIndex(node.index()));
+    }
+    is_match || any_child
+}
+
 /// Copied from https://bevyengine.org/examples/ui/ui/ last accessed: 2022-02-06
-/// Moves the list if LCtrl is held
+/// Moves the list under the cursor if LCtrl is held
 fn mouse_scroll(
     mut mouse_wheel_events: EventReader<MouseWheel>,
-    mut query_list: Query<(&mut ScrollingList, &mut Style, &Children, &Node)>,
+    mut query_list: Query<(&mut ScrollingList, &mut Style, &Children, &Node, &Parent)>,
     query_item: Query<&Node>,
+    query_panel: Query<(&Node, &GlobalTransform)>,
     keyboard_input: Res<Input<KeyCode>>,
+    windows: Res<Windows>,
 ) {
     if !keyboard_input.pressed(KeyCode::LControl) {
         return;
     }
+    let cursor = match windows
+        .get_primary()
+        .and_then(|window| window.cursor_position())
+    {
+        Some(it) => it,
+        None => return,
+    };
     for mouse_wheel_event in mouse_wheel_events.iter() {
-        for (mut scrolling_list, mut style, children, uinode) in query_list.iter_mut() {
+        for (mut scrolling_list, mut style, children, uinode, parent) in query_list.iter_mut() {
+            // The panel clipping the list, same as the hit test of buttons
+            let hovered = query_panel.get(parent.0).is_ok_and(|(panel, transform)| {
+                let min = transform.translation.truncate() - panel.size / 2.0;
+                let max = transform.translation.truncate() + panel.size / 2.0;
+                (min.x..max.x).contains(&cursor.x) && (min.y..max.y).contains(&cursor.y)
+            });
+            if !hovered {
+                continue;
+            }
             let items_height: f32 = children
                 .iter()
                 .map(|entity| query_item.get(*entity).unwrap().size.y)
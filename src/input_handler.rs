@@ -1,10 +1,29 @@
 use bevy::prelude::*;
 
-use crate::InspectorState;
+use crate::{explosion::ExplosionMode, InspectorState};
+
+/// Explosion factor per second the parts move with
+const EXPLOSION_SPEED: f32 = 1.0;
+/// Shortest time a transition takes, so small steps are still visibly animated
+const MIN_TRANSITION_SECONDS: f32 = 0.3;
+/// The explosion factor a full explode/collapse cycle reaches
+const CYCLE_EXPLOSION_FACTOR: f32 = 1.5;
+
+/// The transition of the shown explosion factor towards
+/// [InspectorState::explosion_target]
+#[derive(Default)]
+pub struct ExplosionAnimation {
+    from: f32,
+    to: f32,
+    elapsed: f32,
+    /// Collapse the model again once it is fully exploded
+    cycling: bool,
+}
 
 // Adjust the scaling factor
 pub fn update_explosion_factor(
     mut state: ResMut<InspectorState>,
+    mut animation: ResMut<ExplosionAnimation>,
     keyboard_input: Res<Input<KeyCode>>,
 ) {
     if !keyboard_input.is_changed() {
@@ -12,14 +31,75 @@ pub fn update_explosion_factor(
     }
     let mut changed = false;
     if keyboard_input.just_pressed(KeyCode::Q) {
-        state.explosion_factor += 0.2;
+        state.explosion_target += 0.2;
         changed = true;
-    } else if keyboard_input.just_pressed(KeyCode::E) && (state.explosion_factor > 0.0) {
-        state.explosion_factor -= 0.2;
+    } else if keyboard_input.just_pressed(KeyCode::E) && (state.explosion_target > 0.0) {
+        state.explosion_target -= 0.2;
         changed = true;
+    } else if keyboard_input.just_pressed(KeyCode::P) {
+        if animation.cycling {
+            // Stop where the parts currently are
+            state.explosion_target = state.explosion_factor;
+        } else {
+            state.explosion_target = CYCLE_EXPLOSION_FACTOR;
+        }
+        animation.cycling = !animation.cycling;
+        return;
+    }
+
+    if changed {
+        animation.cycling = false;
+        if state.explosion_target <= 0.0 {
+            state.explosion_target = 0.0;
+        }
     }
+}
 
-    if changed && state.explosion_factor <= 0.0 {
-        state.explosion_factor = 0.0;
+/// Eases the explosion factor towards its target
+pub fn animate_explosion(
+    time: Res<Time>,
+    mut state: ResMut<InspectorState>,
+    mut animation: ResMut<ExplosionAnimation>,
+) {
+    if animation.to != state.explosion_target {
+        animation.from = state.explosion_factor;
+        animation.to = state.explosion_target;
+        animation.elapsed = 0.0;
+    }
+    if state.explosion_factor == state.explosion_target {
+        if animation.cycling {
+            if state.explosion_target > 0.0 {
+                state.explosion_target = 0.0;
+            } else {
+                animation.cycling = false;
+            }
+        }
+        return;
+    }
+
+    animation.elapsed += time.delta_seconds();
+    let duration =
+        ((animation.to - animation.from).abs() / EXPLOSION_SPEED).max(MIN_TRANSITION_SECONDS);
+    let t = (animation.elapsed / duration).min(1.0);
+    // smoothstep, so the parts accelerate and come to rest gently
+    let eased = t * t * (3.0 - 2.0 * t);
+    state.explosion_factor = if t >= 1.0 {
+        animation.to
+    } else {
+        animation.from + (animation.to - animation.from) * eased
+    };
+}
+
+// Switch between moving the parts radially or by their index
+pub fn update_explosion_mode(
+    mut state: ResMut<InspectorState>,
+    keyboard_input: Res<Input<KeyCode>>,
+) {
+    if !keyboard_input.is_changed() || !keyboard_input.just_pressed(KeyCode::M) {
+        return;
     }
+    state.explosion_mode = match state.explosion_mode {
+        ExplosionMode::Radial => ExplosionMode::Linear,
+        ExplosionMode::Linear => ExplosionMode::Radial,
+    };
 }
@@ -1,30 +1,66 @@
-use bevy::prelude::*;
+use bevy::{
+    diagnostic::{FrameTimeDiagnosticsPlugin, LogDiagnosticsPlugin},
+    prelude::*,
+};
+mod animation;
+use animation::AnimationPlugin;
+mod skinning;
+use skinning::SkinningPlugin;
 mod input_handler;
 use hierarchy::HierarchyPlugin;
-use input_handler::update_explosion_factor;
+use input_handler::{
+    animate_explosion, update_explosion_factor, update_explosion_mode, ExplosionAnimation,
+};
 use selection::SelectionPlugin;
 mod ui;
 use crate::ui::UIPlugin;
 mod camera;
+mod document;
 use crate::camera::*;
+use crate::document::DocumentPlugin;
 mod explosion;
 use crate::explosion::*;
 mod file_picker;
 use crate::file_picker::{File, FilePickerPlugin};
 mod hierarchy;
 use bevy_mod_picking::{InteractablePickingPlugin, PickingPlugin};
+mod cli;
+mod convert;
+use convert::ConvertPlugin;
+mod export;
+use export::ExportPlugin;
+mod materials;
+use materials::MaterialsPlugin;
+mod report;
+mod scene_picker;
+use scene_picker::ScenePickerPlugin;
 mod selection;
+mod statistics;
 mod utils;
+mod validation;
+use validation::ValidationPlugin;
 
 #[derive(Default)]
 pub struct InspectorState {
+    /// The explosion factor the model is currently drawn with
     explosion_factor: f32,
+    /// The explosion factor the model is moving towards
+    explosion_target: f32,
+    explosion_mode: ExplosionMode,
     current_file: Option<&'static File>,
+    /// The scene of the current file that is drawn, None if the file has no
+    /// scenes and its root nodes are drawn instead
+    current_scene: Option<usize>,
 }
 
 fn main() {
-    App::new()
-        .insert_resource(ClearColor(Color::rgb(0.4, 0.4, 0.4)))
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if let Some(code) = cli::run(&args) {
+        std::process::exit(code);
+    }
+
+    let mut app = App::new();
+    app.insert_resource(ClearColor(Color::rgb(0.4, 0.4, 0.4)))
         .insert_resource(WindowDescriptor {
             title: "glTF Inspector".to_string(),
             vsync: true,
@@ -32,17 +68,33 @@ fn main() {
         })
         .insert_resource(Msaa { samples: 4 })
         .init_resource::<InspectorState>()
-        .add_plugins(DefaultPlugins)
-        .add_plugin(PickingPlugin)
+        .init_resource::<ExplosionAnimation>()
+        .add_plugins(DefaultPlugins);
+    // Logs the frames per second and the frame time to compare the cost of changes
+    if std::env::var_os("GLTF_INSPECTOR_FRAME_TIME").is_some() {
+        app.add_plugin(FrameTimeDiagnosticsPlugin)
+            .add_plugin(LogDiagnosticsPlugin::default());
+    }
+    app.add_plugin(PickingPlugin)
         .add_plugin(InteractablePickingPlugin)
         .add_plugin(UIPlugin)
         .add_system(create_light)
         .add_system(update_explosion_factor)
+        .add_system(animate_explosion)
+        .add_system(update_explosion_mode)
         .add_plugin(HierarchyPlugin)
         .add_plugin(ExplosionPlugin)
+        .add_plugin(DocumentPlugin)
         .add_plugin(FilePickerPlugin)
+        .add_plugin(ScenePickerPlugin)
         .add_plugin(CameraPlugin)
         .add_plugin(SelectionPlugin)
+        .add_plugin(AnimationPlugin)
+        .add_plugin(SkinningPlugin)
+        .add_plugin(ValidationPlugin)
+        .add_plugin(ConvertPlugin)
+        .add_plugin(ExportPlugin)
+        .add_plugin(MaterialsPlugin)
         .run();
 }
 
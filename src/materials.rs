@@ -0,0 +1,445 @@
+/// This module implements the browser listing the materials and textures of the opened file
+use std::{collections::HashMap, path::Path};
+
+use bevy::{asset::AssetPath, prelude::*};
+use gltf::{
+    image::Source,
+    material::AlphaMode,
+    texture::{MagFilter, MinFilter, WrappingMode},
+    Document,
+};
+
+use crate::{
+    document::{DocumentLoading, LoadedDocument},
+    file_picker::FileChangedEvent,
+    hierarchy::ScrollingList,
+    ui::{right_section, RightPanel},
+    utils::get_gltf,
+    InspectorState,
+};
+
+pub struct MaterialsPlugin;
+
+impl Plugin for MaterialsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MaterialBrowser>()
+            .add_startup_system_to_stage(StartupStage::PostStartup, spawn_materials_panel)
+            .add_system(toggle_materials_panel)
+            .add_system(update_materials_panel.after(DocumentLoading));
+    }
+}
+
+/// Whether the materials and textures are listed or only counted, switched with T
+#[derive(Default)]
+pub struct MaterialBrowser {
+    pub expanded: bool,
+}
+
+/// A texture a material samples, with the name of the property it is used for
+pub struct TextureSlot {
+    pub slot: &'static str,
+    pub texture: usize,
+    pub tex_coord: u32,
+    /// The scale of normal textures or the strength of occlusion textures
+    pub factor: Option<f32>,
+}
+
+/// The textures a material samples, in the order the specification lists them
+pub fn texture_slots(material: &gltf::Material) -> Vec<TextureSlot> {
+    let pbr = material.pbr_metallic_roughness();
+    let slot = |slot, info: gltf::texture::Info| TextureSlot {
+        slot,
+        texture: info.texture().index(),
+        tex_coord: info.tex_coord(),
+        factor: None,
+    };
+    let mut slots: Vec<TextureSlot> = [
+        pbr.base_color_texture()
+            .map(|info| slot("base color", info)),
+        pbr.metallic_roughness_texture()
+            .map(|info| slot("metallic roughness", info)),
+    ]
+    .into_iter()
+    .flatten()
+    .collect();
+    if let Some(normal) = material.normal_texture() {
+        slots.push(TextureSlot {
+            slot: "normal",
+            texture: normal.texture().index(),
+            tex_coord: normal.tex_coord(),
+            factor: Some(normal.scale()),
+        });
+    }
+    if let Some(occlusion) = material.occlusion_texture() {
+        slots.push(TextureSlot {
+            slot: "occlusion",
+            texture: occlusion.texture().index(),
+            tex_coord: occlusion.tex_coord(),
+            factor: Some(occlusion.strength()),
+        });
+    }
+    slots.extend(
+        material
+            .emissive_texture()
+            .map(|info| slot("emissive", info)),
+    );
+    slots
+}
+
+/// The primitives drawn with each material, `None` standing for the default material
+fn primitives_by_material(gltf: &Document) -> HashMap<Option<usize>, Vec<String>> {
+    let mut primitives: HashMap<Option<usize>, Vec<String>> = HashMap::new();
+    for mesh in gltf.meshes() {
+        for primitive in mesh.primitives() {
+            primitives
+                .entry(primitive.material().index())
+                .or_default()
+                .push(format!(
+                    "mesh {}{} #{}",
+                    mesh.index(),
+                    mesh.name()
+                        .map(|name| format!(" {}", name))
+                        .unwrap_or_default(),
+                    primitive.index()
+                ));
+        }
+    }
+    primitives
+}
+
+/// Joins the first few entries of a list, and counts the rest
+fn abbreviate(entries: &[String]) -> String {
+    const SHOWN_ENTRIES: usize = 4;
+    if entries.is_empty() {
+        return "nothing".to_string();
+    }
+    let mut text = entries
+        .iter()
+        .take(SHOWN_ENTRIES)
+        .cloned()
+        .collect::<Vec<_>>()
+        .join(", ");
+    if entries.len() > SHOWN_ENTRIES {
+        text += &format!(" and {} more", entries.len() - SHOWN_ENTRIES);
+    }
+    text
+}
+
+fn material_text(material: &gltf::Material, primitives: &[String]) -> String {
+    let pbr = material.pbr_metallic_roughness();
+    let [r, g, b, a] = pbr.base_color_factor();
+    let [er, eg, eb] = material.emissive_factor();
+    let alpha_mode = match material.alpha_mode() {
+        AlphaMode::Opaque => "opaque".to_string(),
+        AlphaMode::Mask => format!("mask < {}", material.alpha_cutoff().unwrap_or(0.5)),
+        AlphaMode::Blend => "blend".to_string(),
+    };
+    let mut lines = vec![
+        format!(
+            "Material {}{}",
+            material
+                .index()
+                .map(|index| index.to_string())
+                .unwrap_or_else(|| "default".to_string()),
+            material
+                .name()
+                .map(|name| format!(" {}", name))
+                .unwrap_or_default()
+        ),
+        format!("  base color ({:.2}, {:.2}, {:.2}, {:.2})", r, g, b, a),
+        format!(
+            "  metallic {:.2}, roughness {:.2}",
+            pbr.metallic_factor(),
+            pbr.roughness_factor()
+        ),
+        format!(
+            "  alpha {}, {}",
+            alpha_mode,
+            if material.double_sided() {
+                "double-sided"
+            } else {
+                "single-sided"
+            }
+        ),
+        format!("  emissive ({:.2}, {:.2}, {:.2})", er, eg, eb),
+    ];
+    lines.extend(texture_slots(material).iter().map(|slot| {
+        format!(
+            "  {}: texture {}, TEXCOORD_{}{}",
+            slot.slot,
+            slot.texture,
+            slot.tex_coord,
+            slot.factor
+                .map(|factor| format!(", x{:.2}", factor))
+                .unwrap_or_default()
+        )
+    }));
+    lines.push(format!("  used by {}", abbreviate(primitives)));
+    lines.join("\n")
+}
+
+fn texture_text(texture: &gltf::Texture, loaded: &LoadedDocument, primitives: &[String]) -> String {
+    let image = texture.source();
+    let mime_type = match image.source() {
+        Source::View { mime_type, .. } => Some(mime_type),
+        Source::Uri { mime_type, .. } => mime_type,
+    };
+    let resolution = loaded
+        .images
+        .get(image.index())
+        .map(|data| format!("{}x{} {:?}", data.width, data.height, data.format))
+        .unwrap_or_else(|| "not loaded".to_string());
+    let sampler = texture.sampler();
+    let wrap = |mode| match mode {
+        WrappingMode::ClampToEdge => "clamp",
+        WrappingMode::MirroredRepeat => "mirrored repeat",
+        WrappingMode::Repeat => "repeat",
+    };
+    let mag_filter = match sampler.mag_filter() {
+        Some(MagFilter::Nearest) => "nearest",
+        Some(MagFilter::Linear) => "linear",
+        None => "auto",
+    };
+    let min_filter = match sampler.min_filter() {
+        Some(MinFilter::Nearest) => "nearest",
+        Some(MinFilter::Linear) => "linear",
+        Some(MinFilter::NearestMipmapNearest) => "nearest mipmap nearest",
+        Some(MinFilter::LinearMipmapNearest) => "linear mipmap nearest",
+        Some(MinFilter::NearestMipmapLinear) => "nearest mipmap linear",
+        Some(MinFilter::LinearMipmapLinear) => "linear mipmap linear",
+        None => "auto",
+    };
+    [
+        format!(
+            "Texture {}{}, image {}{}",
+            texture.index(),
+            texture
+                .name()
+                .map(|name| format!(" {}", name))
+                .unwrap_or_default(),
+            image.index(),
+            image
+                .name()
+                .map(|name| format!(" {}", name))
+                .unwrap_or_default()
+        ),
+        format!(
+            "  {}{}",
+            resolution,
+            mime_type
+                .map(|mime_type| format!(", {}", mime_type))
+                .unwrap_or_default()
+        ),
+        format!(
+            "  {}: wrap {}/{}, mag {}, min {}",
+            sampler
+                .index()
+                .map(|index| format!("sampler {}", index))
+                .unwrap_or_else(|| "default sampler".to_string()),
+            wrap(sampler.wrap_s()),
+            wrap(sampler.wrap_t()),
+            mag_filter,
+            min_filter
+        ),
+        format!("  used by {}", abbreviate(primitives)),
+    ]
+    .join("\n")
+}
+
+#[derive(Component)]
+struct MaterialsPanel;
+
+fn spawn_materials_panel(
+    mut commands: Commands,
+    right_panel_query: Query<Entity, With<RightPanel>>,
+) {
+    let mut section = right_section();
+    section.style.max_size = Size::new(Val::Px(280.), Val::Undefined);
+    let panel = commands.spawn_bundle(section).insert(MaterialsPanel).id();
+    commands.entity(right_panel_query.single()).add_child(panel);
+}
+
+fn toggle_materials_panel(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut browser: ResMut<MaterialBrowser>,
+) {
+    if keyboard_input.is_changed() && keyboard_input.just_pressed(KeyCode::T) {
+        browser.expanded = !browser.expanded;
+    }
+}
+
+/// Counts the materials and textures, and when expanded lists them with a
+/// thumbnail of every texture
+fn update_materials_panel(
+    mut reader: EventReader<FileChangedEvent>,
+    mut commands: Commands,
+    browser: Res<MaterialBrowser>,
+    state: Res<InspectorState>,
+    loaded: Res<LoadedDocument>,
+    asset_server: Res<AssetServer>,
+    panel_query: Query<(Entity, Option<&Children>), With<MaterialsPanel>>,
+) {
+    if reader.iter().next().is_none() && !browser.is_changed() {
+        return;
+    }
+    let (panel, children) = panel_query.single();
+    for child in children.iter().flat_map(|children| children.iter()) {
+        commands.entity(*child).despawn_recursive();
+    }
+    let (gltf, file) = match (get_gltf(&loaded), state.current_file) {
+        (Some(gltf), Some(file)) => (gltf, file),
+        _ => return,
+    };
+    let style = |font_size: f32, color: Color| TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size,
+        color,
+    };
+    let detail_color = Color::rgb(0.8, 0.9, 1.0);
+    commands.entity(panel).with_children(|parent| {
+        parent.spawn_bundle(TextBundle {
+            text: Text::with_section(
+                format!(
+                    "Materials: {}, textures: {}, T to {}",
+                    gltf.materials().count(),
+                    gltf.textures().count(),
+                    if browser.expanded { "hide" } else { "show" }
+                ),
+                style(18.0, Color::WHITE),
+                Default::default(),
+            ),
+            ..Default::default()
+        });
+        if !browser.expanded {
+            return;
+        }
+        let primitives = primitives_by_material(gltf);
+        let mut primitives_by_texture: HashMap<usize, Vec<String>> = HashMap::new();
+        for material in gltf.materials() {
+            let users = primitives
+                .get(&material.index())
+                .map(Vec::as_slice)
+                .unwrap_or_default();
+            for slot in texture_slots(&material) {
+                let entry = primitives_by_texture.entry(slot.texture).or_default();
+                for user in users {
+                    if !entry.contains(user) {
+                        entry.push(user.clone());
+                    }
+                }
+            }
+        }
+        // Same as the list of the hierarchy, moved by scrolling with LCtrl held
+        parent
+            .spawn_bundle(NodeBundle {
+                style: Style {
+                    flex_direction: FlexDirection::ColumnReverse,
+                    max_size: Size::new(Val::Px(280.), Val::Px(360.)),
+                    overflow: Overflow::Hidden,
+                    ..Default::default()
+                },
+                color: Color::NONE.into(),
+                ..Default::default()
+            })
+            .with_children(|parent| {
+                parent
+                    .spawn_bundle(NodeBundle {
+                        style: Style {
+                            flex_direction: FlexDirection::ColumnReverse,
+                            flex_grow: 1.0,
+                            ..Default::default()
+                        },
+                        color: Color::NONE.into(),
+                        ..Default::default()
+                    })
+                    .insert(ScrollingList::default())
+                    .with_children(|list| {
+                        for material in gltf.materials() {
+                            let text = material_text(
+                                &material,
+                                primitives
+                                    .get(&material.index())
+                                    .map(Vec::as_slice)
+                                    .unwrap_or_default(),
+                            );
+                            list.spawn_bundle(TextBundle {
+                                text: Text::with_section(
+                                    text,
+                                    style(16.0, detail_color),
+                                    Default::default(),
+                                ),
+                                style: Style {
+                                    max_size: Size::new(Val::Px(280.), Val::Undefined),
+                                    ..Default::default()
+                                },
+                                ..Default::default()
+                            });
+                        }
+
+                        for texture in gltf.textures() {
+                            let text = texture_text(
+                                &texture,
+                                &loaded,
+                                primitives_by_texture
+                                    .get(&texture.index())
+                                    .map(Vec::as_slice)
+                                    .unwrap_or_default(),
+                            );
+                            // Loaded with the rest of the file by the asset server when it was drawn
+                            let label = format!("Texture{}", texture.index());
+                            let image: Handle<Image> = asset_server
+                                .load(AssetPath::new_ref(Path::new(&file.path), Some(&label)));
+                            let (width, height) = loaded
+                                .images
+                                .get(texture.source().index())
+                                .map(|data| (data.width.max(1) as f32, data.height.max(1) as f32))
+                                .unwrap_or((1.0, 1.0));
+                            const THUMBNAIL_SIZE: f32 = 48.0;
+                            let scale = THUMBNAIL_SIZE / width.max(height);
+                            list.spawn_bundle(NodeBundle {
+                                style: Style {
+                                    flex_direction: FlexDirection::Row,
+                                    align_items: AlignItems::FlexStart,
+                                    margin: Rect {
+                                        top: Val::Px(4.0),
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                },
+                                color: Color::NONE.into(),
+                                ..Default::default()
+                            })
+                            .with_children(|parent| {
+                                parent.spawn_bundle(ImageBundle {
+                                    image: image.into(),
+                                    style: Style {
+                                        size: Size::new(
+                                            Val::Px(width * scale),
+                                            Val::Px(height * scale),
+                                        ),
+                                        margin: Rect {
+                                            right: Val::Px(6.0),
+                                            ..Default::default()
+                                        },
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                });
+                                parent.spawn_bundle(TextBundle {
+                                    text: Text::with_section(
+                                        text,
+                                        style(14.0, detail_color),
+                                        Default::default(),
+                                    ),
+                                    style: Style {
+                                        max_size: Size::new(Val::Px(220.), Val::Undefined),
+                                        ..Default::default()
+                                    },
+                                    ..Default::default()
+                                });
+                            });
+                        }
+                    });
+            });
+    });
+}
@@ -0,0 +1,386 @@
+use serde::Serialize;
+
+use crate::{
+    animation::read_animation,
+    document::{FileLayout, LoadedDocument},
+    hierarchy::{traverse_gltf, TreeEntry},
+    materials::texture_slots,
+    statistics::Statistics,
+    utils::{format_bytes, meshcount_for_nodes, scene_roots},
+};
+
+/// The structure of a glTF file, as printed by the headless `inspect` command
+#[derive(Serialize)]
+pub struct Report {
+    pub file: String,
+    pub layout: Option<FileLayout>,
+    pub default_scene: Option<usize>,
+    pub scenes: Vec<SceneReport>,
+    pub meshes: Vec<MeshReport>,
+    pub materials: Vec<MaterialReport>,
+    pub textures: Vec<TextureReport>,
+    pub animations: Vec<AnimationReport>,
+    pub counts: Counts,
+}
+
+#[derive(Serialize)]
+pub struct SceneReport {
+    /// None for the root nodes of a file without scenes
+    pub index: Option<usize>,
+    pub name: Option<String>,
+    pub mesh_count: usize,
+    pub statistics: Statistics,
+    /// The rows of the hierarchy, indented by their depth
+    pub tree: Vec<TreeLine>,
+}
+
+#[derive(Serialize)]
+pub struct TreeLine {
+    pub depth: usize,
+    pub label: String,
+}
+
+#[derive(Serialize)]
+pub struct MeshReport {
+    pub index: usize,
+    pub name: Option<String>,
+    pub primitives: Vec<PrimitiveReport>,
+}
+
+#[derive(Serialize)]
+pub struct PrimitiveReport {
+    pub index: usize,
+    pub mode: String,
+    pub vertices: usize,
+    pub indices: Option<usize>,
+    pub attributes: Vec<String>,
+    pub material: Option<usize>,
+    pub morph_targets: usize,
+}
+
+#[derive(Serialize)]
+pub struct MaterialReport {
+    pub index: Option<usize>,
+    pub name: Option<String>,
+    pub base_color: [f32; 4],
+    pub metallic: f32,
+    pub roughness: f32,
+    pub emissive: [f32; 3],
+    pub alpha_mode: String,
+    pub double_sided: bool,
+    /// The texture slots in use and the index of their texture
+    pub textures: Vec<(String, usize)>,
+}
+
+#[derive(Serialize)]
+pub struct TextureReport {
+    pub index: usize,
+    pub name: Option<String>,
+    pub image: usize,
+    pub width: Option<u32>,
+    pub height: Option<u32>,
+}
+
+#[derive(Serialize)]
+pub struct AnimationReport {
+    pub index: usize,
+    pub name: String,
+    pub duration: f32,
+    /// The animated property of every channel, with the node it targets
+    pub channels: Vec<String>,
+}
+
+#[derive(Serialize)]
+pub struct Counts {
+    pub scenes: usize,
+    pub nodes: usize,
+    pub meshes: usize,
+    pub primitives: usize,
+    pub materials: usize,
+    pub textures: usize,
+    pub images: usize,
+    pub animations: usize,
+    pub skins: usize,
+    pub cameras: usize,
+}
+
+impl Report {
+    pub fn new(file: &str, loaded: &LoadedDocument) -> Option<Self> {
+        let gltf = loaded.document.as_ref()?;
+        // Files without scenes are reported with their root nodes, as the inspector shows them
+        let scene_indices: Vec<Option<usize>> = match gltf.scenes().count() {
+            0 => vec![None],
+            count => (0..count).map(Some).collect(),
+        };
+        let scenes = scene_indices
+            .into_iter()
+            .map(|index| {
+                let roots = scene_roots(gltf, index);
+                let mut entries: Vec<TreeEntry> = vec![];
+                for node in roots.iter().cloned() {
+                    traverse_gltf(node, &mut vec![], &mut entries);
+                }
+                SceneReport {
+                    index,
+                    name: index
+                        .and_then(|index| gltf.scenes().nth(index))
+                        .and_then(|scene| scene.name())
+                        .map(str::to_string),
+                    mesh_count: meshcount_for_nodes(roots.iter().cloned()),
+                    statistics: Statistics::of_scene(roots, gltf, loaded),
+                    tree: entries
+                        .into_iter()
+                        .map(|entry| TreeLine {
+                            depth: entry.ancestors.len(),
+                            label: entry.label,
+                        })
+                        .collect(),
+                }
+            })
+            .collect();
+        let meshes = gltf
+            .meshes()
+            .map(|mesh| MeshReport {
+                index: mesh.index(),
+                name: mesh.name().map(str::to_string),
+                primitives: mesh
+                    .primitives()
+                    .map(|primitive| PrimitiveReport {
+                        index: primitive.index(),
+                        mode: format!("{:?}", primitive.mode()),
+                        vertices: primitive
+                            .get(&gltf::Semantic::Positions)
+                            .map(|positions| positions.count())
+                            .unwrap_or_default(),
+                        indices: primitive.indices().map(|indices| indices.count()),
+                        attributes: {
+                            let mut attributes: Vec<String> = primitive
+                                .attributes()
+                                .map(|(semantic, _)| semantic.to_string())
+                                .collect();
+                            attributes.sort();
+                            attributes
+                        },
+                        material: primitive.material().index(),
+                        morph_targets: primitive.morph_targets().count(),
+                    })
+                    .collect(),
+            })
+            .collect();
+        let materials = gltf.materials().map(material_report).collect();
+        let textures = gltf
+            .textures()
+            .map(|texture| {
+                let image = loaded.images.get(texture.source().index());
+                TextureReport {
+                    index: texture.index(),
+                    name: texture.name().map(str::to_string),
+                    image: texture.source().index(),
+                    width: image.map(|image| image.width),
+                    height: image.map(|image| image.height),
+                }
+            })
+            .collect();
+        let animations = gltf
+            .animations()
+            .map(|animation| {
+                let index = animation.index();
+                let clip = read_animation(animation, loaded);
+                AnimationReport {
+                    index,
+                    name: clip.name,
+                    duration: clip.duration,
+                    channels: clip
+                        .channels
+                        .iter()
+                        .map(|channel| format!("Node {} {}", channel.node.0, channel.property()))
+                        .collect(),
+                }
+            })
+            .collect();
+        let counts = Counts {
+            scenes: gltf.scenes().count(),
+            nodes: gltf.nodes().count(),
+            meshes: gltf.meshes().count(),
+            primitives: gltf.meshes().map(|mesh| mesh.primitives().count()).sum(),
+            materials: gltf.materials().count(),
+            textures: gltf.textures().count(),
+            images: gltf.images().count(),
+            animations: gltf.animations().count(),
+            skins: gltf.skins().count(),
+            cameras: gltf.cameras().count(),
+        };
+
+        Some(Report {
+            file: file.to_string(),
+            layout: loaded.layout.clone(),
+            default_scene: gltf.default_scene().map(|scene| scene.index()),
+            scenes,
+            meshes,
+            materials,
+            textures,
+            animations,
+            counts,
+        })
+    }
+
+    /// The report as indented text, in the order of the JSON fields
+    pub fn to_text(&self) -> String {
+        let mut lines = vec![format!("File: {}", self.file)];
+        if let Some(layout) = &self.layout {
+            lines.push(format!(
+                "Container: {}",
+                layout.to_text().replace('\n', "\n  ")
+            ));
+            for resource in &layout.resources {
+                lines.push(format!(
+                    "  {}: {}{}{}",
+                    resource.pointer,
+                    resource.storage,
+                    resource
+                        .uri
+                        .as_ref()
+                        .map(|uri| format!(" {}", uri))
+                        .unwrap_or_default(),
+                    resource
+                        .size
+                        .map(|size| format!(", {}", format_bytes(size)))
+                        .unwrap_or_default()
+                ));
+            }
+        }
+        let default_scene = self
+            .default_scene
+            .map(|index| index.to_string())
+            .unwrap_or_else(|| "none".to_string());
+        lines.push(format!("Default scene: {}", default_scene));
+        for scene in &self.scenes {
+            let title = match scene.index {
+                Some(index) => format!("Scene {}{}", index, name_suffix(&scene.name)),
+                None => "Root nodes".to_string(),
+            };
+            lines.push(format!("{}: {} meshes", title, scene.mesh_count));
+            for line in scene.statistics.to_text().lines() {
+                lines.push(format!("  {}", line));
+            }
+            for row in &scene.tree {
+                lines.push(format!("{}{}", "  ".repeat(row.depth + 1), row.label));
+            }
+        }
+        lines.push("Meshes:".to_string());
+        for mesh in &self.meshes {
+            lines.push(format!("  Mesh {}{}", mesh.index, name_suffix(&mesh.name)));
+            for primitive in &mesh.primitives {
+                let indices = primitive
+                    .indices
+                    .map(|count| count.to_string())
+                    .unwrap_or_else(|| "no".to_string());
+                let material = primitive
+                    .material
+                    .map(|index| index.to_string())
+                    .unwrap_or_else(|| "default".to_string());
+                lines.push(format!(
+                    "    Primitive {} ({}): {} vertices, {} indices, material {}, {} morph targets, attributes {}",
+                    primitive.index,
+                    primitive.mode,
+                    primitive.vertices,
+                    indices,
+                    material,
+                    primitive.morph_targets,
+                    primitive.attributes.join(", ")
+                ));
+            }
+        }
+        lines.push("Materials:".to_string());
+        for material in &self.materials {
+            let textures: Vec<String> = material
+                .textures
+                .iter()
+                .map(|(slot, index)| format!("{} #{}", slot, index))
+                .collect();
+            lines.push(format!(
+                "  Material {}{}: base color {:.2?}, metallic {:.2}, roughness {:.2}, emissive {:.2?}, {} alpha{}{}",
+                material.index.map(|index| index.to_string()).unwrap_or_default(),
+                name_suffix(&material.name),
+                material.base_color,
+                material.metallic,
+                material.roughness,
+                material.emissive,
+                material.alpha_mode,
+                if material.double_sided { ", double sided" } else { "" },
+                if textures.is_empty() {
+                    String::new()
+                } else {
+                    format!(", textures {}", textures.join(", "))
+                }
+            ));
+        }
+        lines.push("Textures:".to_string());
+        for texture in &self.textures {
+            let size = texture
+                .width
+                .zip(texture.height)
+                .map(|(width, height)| format!(" ({}x{})", width, height))
+                .unwrap_or_default();
+            lines.push(format!(
+                "  Texture {}{}: image {}{}",
+                texture.index,
+                name_suffix(&texture.name),
+                texture.image,
+                size
+            ));
+        }
+        lines.push("Animations:".to_string());
+        for animation in &self.animations {
+            lines.push(format!(
+                "  Animation {} {}: {:.2} s, {} channels",
+                animation.index,
+                animation.name,
+                animation.duration,
+                animation.channels.len()
+            ));
+            for channel in &animation.channels {
+                lines.push(format!("    {}", channel));
+            }
+        }
+        let counts = &self.counts;
+        lines.push(format!(
+            "Counts: {} scenes, {} nodes, {} meshes, {} primitives, {} materials, {} textures, {} images, {} animations, {} skins, {} cameras",
+            counts.scenes,
+            counts.nodes,
+            counts.meshes,
+            counts.primitives,
+            counts.materials,
+            counts.textures,
+            counts.images,
+            counts.animations,
+            counts.skins,
+            counts.cameras
+        ));
+        lines.join("\n")
+    }
+}
+
+fn material_report(material: gltf::Material) -> MaterialReport {
+    let pbr = material.pbr_metallic_roughness();
+    MaterialReport {
+        index: material.index(),
+        name: material.name().map(str::to_string),
+        base_color: pbr.base_color_factor(),
+        metallic: pbr.metallic_factor(),
+        roughness: pbr.roughness_factor(),
+        emissive: material.emissive_factor(),
+        alpha_mode: format!("{:?}", material.alpha_mode()),
+        double_sided: material.double_sided(),
+        textures: texture_slots(&material)
+            .into_iter()
+            .map(|slot| (slot.slot.to_string(), slot.texture))
+            .collect(),
+    }
+}
+
+fn name_suffix(name: &Option<String>) -> String {
+    name.as_ref()
+        .map(|name| format!(" {}", name))
+        .unwrap_or_default()
+}
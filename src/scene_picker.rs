@@ -0,0 +1,178 @@
+/// This module implements the systems for switching between the scenes of a file
+use bevy::prelude::*;
+
+use crate::{
+    document::{DocumentLoading, LoadedDocument},
+    file_picker::FileChangedEvent,
+    ui::{right_section, RightPanel},
+    utils::{default_scene_index, get_gltf},
+    InspectorState,
+};
+
+/// Systems drawing the current scene need to run after it has been chosen,
+/// which happens whenever another file has been loaded
+#[derive(Clone, Hash, Debug, PartialEq, Eq, SystemLabel)]
+pub struct SceneSelection;
+
+/// Sent after another scene has been chosen, including the first scene of a newly opened file
+pub struct SceneChangedEvent;
+
+pub struct ScenePickerPlugin;
+
+impl Plugin for ScenePickerPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_event::<SceneChangedEvent>()
+            .add_startup_system_to_stage(StartupStage::PostStartup, spawn_scene_panel)
+            .add_system(
+                select_initial_scene
+                    .label(SceneSelection)
+                    .after(DocumentLoading),
+            )
+            .add_system(switch_between_scenes.label(SceneSelection))
+            .add_system(scene_buttons.label(SceneSelection))
+            .add_system(update_scene_panel.after(SceneSelection));
+    }
+}
+
+/// Shows the scene of the file's preset, or else the default scene of the document
+fn select_initial_scene(
+    mut reader: EventReader<FileChangedEvent>,
+    mut state: ResMut<InspectorState>,
+    loaded: Res<LoadedDocument>,
+    mut writer: EventWriter<SceneChangedEvent>,
+) {
+    if reader.iter().next().is_none() {
+        return;
+    }
+    state.current_scene = get_gltf(&loaded).and_then(|gltf| {
+        state
+            .current_file
+            .and_then(|file| file.scene_index)
+            .filter(|index| *index < gltf.scenes().count())
+            .or_else(|| default_scene_index(gltf))
+    });
+    writer.send(SceneChangedEvent);
+}
+
+fn switch_between_scenes(
+    mut state: ResMut<InspectorState>,
+    keyboard_input: Res<Input<KeyCode>>,
+    loaded: Res<LoadedDocument>,
+    mut writer: EventWriter<SceneChangedEvent>,
+) {
+    if !keyboard_input.is_changed() {
+        return;
+    }
+    let (count, current) = match (get_gltf(&loaded), state.current_scene) {
+        (Some(gltf), Some(current)) => (gltf.scenes().count(), current),
+        _ => return,
+    };
+    if count < 2 {
+        return;
+    }
+    if keyboard_input.just_pressed(KeyCode::Up) {
+        state.current_scene = Some((current + count - 1) % count);
+        writer.send(SceneChangedEvent);
+    } else if keyboard_input.just_pressed(KeyCode::Down) {
+        state.current_scene = Some((current + 1) % count);
+        writer.send(SceneChangedEvent);
+    }
+}
+
+/// A row of the scene list, clicking on it shows the scene
+#[derive(Component, Clone, Copy)]
+struct SceneButton(usize);
+
+#[derive(Component)]
+struct ScenePanel;
+
+fn spawn_scene_panel(mut commands: Commands, right_panel_query: Query<Entity, With<RightPanel>>) {
+    let panel = commands
+        .spawn_bundle(right_section())
+        .insert(ScenePanel)
+        .id();
+    // Below the errors, above the instructions
+    commands
+        .entity(right_panel_query.single())
+        .insert_children(1, &[panel]);
+}
+
+fn scene_buttons(
+    interaction_query: Query<(&Interaction, &SceneButton), Changed<Interaction>>,
+    mut state: ResMut<InspectorState>,
+    mut writer: EventWriter<SceneChangedEvent>,
+) {
+    for (_, button) in interaction_query
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Clicked)
+    {
+        if state.current_scene != Some(button.0) {
+            state.current_scene = Some(button.0);
+            writer.send(SceneChangedEvent);
+        }
+    }
+}
+
+/// Lists the scenes of the file with the current one highlighted, or notes
+/// that the root nodes are shown if the file has no scenes
+fn update_scene_panel(
+    mut reader: EventReader<SceneChangedEvent>,
+    mut commands: Commands,
+    state: Res<InspectorState>,
+    loaded: Res<LoadedDocument>,
+    asset_server: Res<AssetServer>,
+    panel_query: Query<(Entity, Option<&Children>), With<ScenePanel>>,
+) {
+    if reader.iter().next().is_none() {
+        return;
+    }
+    let (panel, children) = panel_query.single();
+    for child in children.iter().flat_map(|children| children.iter()) {
+        commands.entity(*child).despawn_recursive();
+    }
+    let gltf = match get_gltf(&loaded) {
+        Some(it) => it,
+        _ => return,
+    };
+    let style = |color: Color| TextStyle {
+        font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+        font_size: 18.0,
+        color,
+    };
+    commands.entity(panel).with_children(|parent| {
+        let title = match gltf.scenes().count() {
+            0 => "No scenes, showing the root nodes".to_string(),
+            count => format!("Scenes ({}), Up+Down Arrows to switch:", count),
+        };
+        parent.spawn_bundle(TextBundle {
+            text: Text::with_section(title, style(Color::WHITE), Default::default()),
+            ..Default::default()
+        });
+        for scene in gltf.scenes() {
+            let current = state.current_scene == Some(scene.index());
+            let default = gltf.default_scene().map(|it| it.index()) == Some(scene.index());
+            let label = format!(
+                "{} Scene {}{}{}",
+                if current { ">" } else { " " },
+                scene.index(),
+                scene
+                    .name()
+                    .map(|name| format!(" {}", name))
+                    .unwrap_or_default(),
+                if default { " (default)" } else { "" }
+            );
+            let color = if current {
+                Color::rgb(1.0, 1.0, 0.0)
+            } else {
+                Color::WHITE
+            };
+            parent
+                .spawn_bundle(TextBundle {
+                    text: Text::with_section(label, style(color), Default::default()),
+                    ..Default::default()
+                })
+                .insert(Interaction::default())
+                .insert(SceneButton(scene.index()));
+        }
+    });
+}
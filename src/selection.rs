@@ -1,19 +1,65 @@
 use bevy::prelude::*;
 use bevy_mod_picking::PickingEvent;
 
-use crate::hierarchy::{MeshIndex, NodeIndex, PrimitiveIdentifier};
+use crate::{
+    camera::FocusEvent,
+    hierarchy::{HierarchyButton, MeshIndex, NodeIndex, PrimitiveIdentifier},
+    scene_picker::SceneChangedEvent,
+};
 
 pub struct SelectionPlugin;
 
 impl Plugin for SelectionPlugin {
     fn build(&self, app: &mut App) {
-        app.add_system(highlight_path).add_system(clear_on_escape);
+        app.init_resource::<Selection>()
+            .add_system(highlight_path)
+            .add_event::<SelectNodesEvent>()
+            .add_system(select_from_hierarchy)
+            .add_system(select_nodes)
+            .add_system(clear_on_escape)
+            .add_system(clear_on_scene_change)
+            .add_system(toggle_subtree_highlight)
+            .add_system(highlight_selection);
     }
 }
+
+/// The primitives that were last clicked on, either on screen or in the list
+#[derive(Default)]
+pub struct Selection {
+    /// The primitive whose details are shown
+    pub primitive: Option<PrimitiveIdentifier>,
+    /// The entities of all selected primitives
+    pub entities: Vec<Entity>,
+    /// Highlight every primitive of the nodes owning the selected primitives,
+    /// including their children, instead of only the primitives themselves
+    pub highlight_subtree: bool,
+}
+
+impl Selection {
+    fn clear(&mut self) {
+        self.primitive = None;
+        self.entities.clear();
+    }
+}
+
+/// Selects the primitives of nodes in the scene
+pub struct SelectNodesEvent {
+    pub nodes: Vec<NodeIndex>,
+    /// Whether the primitives of the children of the nodes are selected as well
+    pub include_children: bool,
+}
+
+/// Marks a primitive whose material was replaced by a tinted copy
+#[derive(Component)]
+struct Highlighted {
+    original: Handle<StandardMaterial>,
+}
+
 /// Colors all Nodes and Meshes in the list that are on the path of a
 /// clicked on primitive on screen
 fn highlight_path(
     mut events: EventReader<PickingEvent>,
+    mut selection: ResMut<Selection>,
     mesh_query: Query<(Entity, &Parent, &PrimitiveIdentifier)>,
     node_query: Query<(Entity, &Parent, &NodeIndex)>,
     mut ui_nodes_query: Query<(&mut Text, &NodeIndex), Without<MeshIndex>>,
@@ -25,7 +71,10 @@ fn highlight_path(
     }) {
         clear_nodes(&mut ui_nodes_query);
         clear_mesh(&mut ui_mesh_query);
-        if let Ok((_, parent, primitive_identifier)) = mesh_query.get(*event) {
+        selection.clear();
+        if let Ok((entity, parent, primitive_identifier)) = mesh_query.get(*event) {
+            selection.primitive = Some(*primitive_identifier);
+            selection.entities.push(entity);
             let node_indices = chain_node_indices(parent.0, &node_query);
             color_nodes(&node_indices, &mut ui_nodes_query);
             color_mesh(primitive_identifier.mesh_index, &mut ui_mesh_query);
@@ -33,8 +82,71 @@ fn highlight_path(
     }
 }
 
+/// Selects the primitives of a node when its entry in the list is clicked
+fn select_from_hierarchy(
+    interaction_query: Query<(&Interaction, &HierarchyButton), Changed<Interaction>>,
+    mut writer: EventWriter<SelectNodesEvent>,
+) {
+    for (_, button) in interaction_query
+        .iter()
+        .filter(|(interaction, _)| **interaction == Interaction::Clicked)
+    {
+        writer.send(SelectNodesEvent {
+            nodes: vec![button.node],
+            include_children: button.include_children,
+        });
+    }
+}
+
+/// Selects the primitives of the given nodes, colors their paths
+/// and focuses the camera on them
+#[allow(clippy::too_many_arguments)]
+fn select_nodes(
+    mut events: EventReader<SelectNodesEvent>,
+    mut selection: ResMut<Selection>,
+    mut focus_writer: EventWriter<FocusEvent>,
+    scene_node_query: Query<(Entity, &NodeIndex), Without<Node>>,
+    node_query: Query<(Entity, &Parent, &NodeIndex)>,
+    primitive_query: Query<&PrimitiveIdentifier>,
+    children_query: Query<&Children>,
+    mut ui_nodes_query: Query<(&mut Text, &NodeIndex), Without<MeshIndex>>,
+    mut ui_mesh_query: Query<(&mut Text, &MeshIndex), Without<NodeIndex>>,
+) {
+    for event in events.iter() {
+        clear_nodes(&mut ui_nodes_query);
+        clear_mesh(&mut ui_mesh_query);
+        selection.clear();
+
+        let mut node_indices = vec![];
+        for (node_entity, _) in scene_node_query
+            .iter()
+            .filter(|(_, index)| event.nodes.contains(index))
+        {
+            let candidates = if event.include_children {
+                subtree(node_entity, &children_query)
+            } else {
+                children_query
+                    .get(node_entity)
+                    .map(|children| children.iter().copied().collect())
+                    .unwrap_or_default()
+            };
+            for entity in candidates {
+                if let Ok(primitive_identifier) = primitive_query.get(entity) {
+                    selection.primitive.get_or_insert(*primitive_identifier);
+                    selection.entities.push(entity);
+                    color_mesh(primitive_identifier.mesh_index, &mut ui_mesh_query);
+                }
+            }
+            node_indices.append(&mut chain_node_indices(node_entity, &node_query));
+        }
+        color_nodes(&node_indices, &mut ui_nodes_query);
+        focus_writer.send(FocusEvent(selection.entities.clone()));
+    }
+}
+
 fn clear_on_escape(
     keyboard_input: Res<Input<KeyCode>>,
+    mut selection: ResMut<Selection>,
     mut ui_nodes_query: Query<(&mut Text, &NodeIndex), Without<MeshIndex>>,
     mut ui_mesh_query: Query<(&mut Text, &MeshIndex), Without<NodeIndex>>,
 ) {
@@ -43,6 +155,80 @@ fn clear_on_escape(
     }
     clear_nodes(&mut ui_nodes_query);
     clear_mesh(&mut ui_mesh_query);
+    selection.clear();
+}
+
+/// The entities of the previous scene are gone once another file or scene is opened
+fn clear_on_scene_change(
+    mut reader: EventReader<SceneChangedEvent>,
+    mut selection: ResMut<Selection>,
+) {
+    if reader.iter().next().is_some() {
+        selection.clear();
+    }
+}
+
+fn toggle_subtree_highlight(keyboard_input: Res<Input<KeyCode>>, mut selection: ResMut<Selection>) {
+    if keyboard_input.is_changed() && keyboard_input.just_pressed(KeyCode::H) {
+        selection.highlight_subtree = !selection.highlight_subtree;
+    }
+}
+
+/// Tints the selected primitives in the viewport by giving them a copy of
+/// their material, and restores the original material once they are deselected
+fn highlight_selection(
+    mut commands: Commands,
+    selection: Res<Selection>,
+    mut materials: ResMut<Assets<StandardMaterial>>,
+    mut highlighted_query: Query<(Entity, &mut Handle<StandardMaterial>, &Highlighted)>,
+    mut primitive_query: Query<
+        &mut Handle<StandardMaterial>,
+        (With<PrimitiveIdentifier>, Without<Highlighted>),
+    >,
+    parent_query: Query<&Parent>,
+    children_query: Query<&Children>,
+) {
+    if !selection.is_changed() {
+        return;
+    }
+    let mut targets = vec![];
+    for entity in selection.entities.iter().copied() {
+        match parent_query.get(entity) {
+            Ok(parent) if selection.highlight_subtree => {
+                targets.append(&mut subtree(parent.0, &children_query))
+            }
+            _ => targets.push(entity),
+        }
+    }
+    targets.sort();
+    targets.dedup();
+
+    for (entity, mut material, highlighted) in highlighted_query.iter_mut() {
+        if !targets.contains(&entity) {
+            *material = highlighted.original.clone();
+            commands.entity(entity).remove::<Highlighted>();
+        }
+    }
+    for entity in targets {
+        if let Ok(mut material) = primitive_query.get_mut(entity) {
+            let original = material.clone();
+            let mut tinted = materials.get(&original).cloned().unwrap_or_default();
+            tinted.base_color = Color::GREEN;
+            tinted.emissive = Color::rgb(0.0, 0.3, 0.0);
+            *material = materials.add(tinted);
+            commands.entity(entity).insert(Highlighted { original });
+        }
+    }
+}
+
+fn subtree(entity: Entity, children_query: &Query<&Children>) -> Vec<Entity> {
+    let mut ret_val = vec![entity];
+    if let Ok(children) = children_query.get(entity) {
+        for child in children.iter() {
+            ret_val.append(&mut subtree(*child, children_query));
+        }
+    }
+    ret_val
 }
 
 fn chain_node_indices(
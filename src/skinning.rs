@@ -0,0 +1,372 @@
+use std::collections::HashMap;
+
+use bevy::{
+    prelude::*,
+    render::{mesh::VertexAttributeValues, primitives::Aabb},
+    transform::TransformSystem,
+};
+use gltf::Document;
+
+use crate::{document::LoadedDocument, hierarchy::NodeIndex, hierarchy::PrimitiveIdentifier};
+
+/// The renderer draws meshes as they are stored, so this plugin moves the
+/// vertices of skinned primitives and primitives with morph targets on the CPU.
+/// The default morph target weights are applied once, and skinned vertices
+/// follow their joints whenever those are moved by the explosion or an animation.
+pub struct SkinningPlugin;
+
+impl Plugin for SkinningPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_system(prepare_deformation).add_system_to_stage(
+            CoreStage::PostUpdate,
+            skin_vertices.after(TransformSystem::TransformPropagate),
+        );
+    }
+}
+
+/// Marks a drawn primitive whose vertices are deformed by a skin or morph targets
+#[derive(Component)]
+pub struct Deformation {
+    /// The index of the skin of the node the primitive belongs to
+    pub skin: Option<usize>,
+    /// The weights of the morph targets of the primitive
+    pub weights: Vec<f32>,
+}
+
+impl Deformation {
+    /// Returns the deformation of the primitives of `node`, if they are deformed at all
+    pub fn of_node(node: &gltf::Node) -> Option<Self> {
+        let mesh = node.mesh()?;
+        let weights = node
+            .weights()
+            .or_else(|| mesh.weights())
+            .map(|weights| weights.to_vec())
+            .unwrap_or_default();
+        let skin = node.skin().map(|skin| skin.index());
+        let has_targets = mesh
+            .primitives()
+            .any(|primitive| primitive.morph_targets().next().is_some());
+        (skin.is_some() || (has_targets && weights.iter().any(|weight| *weight != 0.0)))
+            .then_some(Self { skin, weights })
+    }
+}
+
+/// The vertices of a skinned primitive in its bind pose
+#[derive(Component)]
+struct SkinnedVertices {
+    positions: Vec<Vec3>,
+    normals: Vec<Vec3>,
+    joints: Vec<[u16; 4]>,
+    weights: Vec<[f32; 4]>,
+    joint_nodes: Vec<NodeIndex>,
+    inverse_bind_matrices: Vec<Mat4>,
+    /// The joint matrices the vertices were last skinned with
+    joint_matrices: Vec<Mat4>,
+}
+
+/// Gives every deformed primitive its own copy of the mesh, with the morph
+/// targets applied to it
+fn prepare_deformation(
+    mut commands: Commands,
+    loaded: Res<LoadedDocument>,
+    mut meshes: ResMut<Assets<Mesh>>,
+    query: Query<(Entity, &PrimitiveIdentifier, &Deformation, &Handle<Mesh>), Added<Deformation>>,
+) {
+    let document = match &loaded.document {
+        Some(it) => it,
+        _ => return,
+    };
+    for (entity, identifier, deformation, handle) in query.iter() {
+        let mut mesh = match meshes.get(handle) {
+            Some(it) => it.clone(),
+            _ => continue,
+        };
+        let vertices = match read_vertices(document, &loaded, identifier, deformation) {
+            Some(it) => it,
+            _ => continue,
+        };
+        if vertices.positions.len() != mesh.count_vertices() {
+            // Happens when normals were missing and the loader generated flat ones
+            warn!(
+                "Cannot deform mesh {} primitive {}, its vertices differ from the file",
+                identifier.mesh_index.0, identifier.primitive_index
+            );
+            continue;
+        }
+        set_vertices(&mut mesh, &vertices.positions, &vertices.normals);
+        // The bounds of the shared mesh are computed again for the deformed one
+        commands
+            .entity(entity)
+            .insert(meshes.add(mesh))
+            .remove::<Aabb>();
+        let count = vertices.positions.len();
+        if vertices.joint_nodes.is_empty()
+            || vertices.joints.len() != count
+            || vertices.weights.len() != count
+        {
+            continue;
+        }
+        commands.entity(entity).insert(vertices);
+    }
+}
+
+fn read_vertices(
+    document: &Document,
+    loaded: &LoadedDocument,
+    identifier: &PrimitiveIdentifier,
+    deformation: &Deformation,
+) -> Option<SkinnedVertices> {
+    let get_buffer =
+        |buffer: gltf::Buffer| loaded.buffers.get(buffer.index()).map(|data| &data[..]);
+    let primitive = document
+        .meshes()
+        .nth(identifier.mesh_index.0)?
+        .primitives()
+        .nth(identifier.primitive_index)?;
+    let skin = deformation
+        .skin
+        .and_then(|index| document.skins().nth(index));
+    // The readers panic on accessors that leave their buffers
+    let mut accessors = primitive
+        .attributes()
+        .map(|(_, accessor)| accessor)
+        .chain(primitive.morph_targets().flat_map(|target| {
+            [target.positions(), target.normals(), target.tangents()]
+                .into_iter()
+                .flatten()
+        }))
+        .chain(skin.as_ref().and_then(|skin| skin.inverse_bind_matrices()));
+    if let Some(accessor) = accessors.find(|accessor| !loaded.accessor_in_bounds(accessor)) {
+        warn!(
+            "Cannot deform mesh {} primitive {}, accessor {} does not fit its buffer",
+            identifier.mesh_index.0,
+            identifier.primitive_index,
+            accessor.index()
+        );
+        return None;
+    }
+    let reader = primitive.reader(get_buffer);
+    let mut positions: Vec<Vec3> = reader.read_positions()?.map(Vec3::from).collect();
+    let mut normals: Vec<Vec3> = reader
+        .read_normals()
+        .map(|normals| normals.map(Vec3::from).collect())
+        .unwrap_or_default();
+
+    for ((position_offsets, normal_offsets, _), weight) in
+        reader.read_morph_targets().zip(&deformation.weights)
+    {
+        if let Some(offsets) = position_offsets {
+            for (position, offset) in positions.iter_mut().zip(offsets) {
+                *position += *weight * Vec3::from(offset);
+            }
+        }
+        if let Some(offsets) = normal_offsets {
+            for (normal, offset) in normals.iter_mut().zip(offsets) {
+                *normal += *weight * Vec3::from(offset);
+            }
+        }
+    }
+
+    let mut vertices = SkinnedVertices {
+        positions,
+        normals,
+        joints: vec![],
+        weights: vec![],
+        joint_nodes: vec![],
+        inverse_bind_matrices: vec![],
+        joint_matrices: vec![],
+    };
+    if let Some(skin) = skin {
+        vertices.joints = reader
+            .read_joints(0)
+            .map(|joints| joints.into_u16().collect())
+            .unwrap_or_default();
+        vertices.weights = reader
+            .read_weights(0)
+            .map(|weights| weights.into_f32().collect())
+            .unwrap_or_default();
+        vertices.joint_nodes = skin
+            .joints()
+            .map(|joint| NodeIndex(joint.index()))
+            .collect();
+        vertices.inverse_bind_matrices = skin
+            .reader(get_buffer)
+            .read_inverse_bind_matrices()
+            .map(|matrices| matrices.map(|it| Mat4::from_cols_array_2d(&it)).collect())
+            .unwrap_or_else(|| vec![Mat4::IDENTITY; vertices.joint_nodes.len()]);
+    }
+    Some(vertices)
+}
+
+fn set_vertices(mesh: &mut Mesh, positions: &[Vec3], normals: &[Vec3]) {
+    mesh.set_attribute(
+        Mesh::ATTRIBUTE_POSITION,
+        positions.iter().map(|it| it.to_array()).collect::<Vec<_>>(),
+    );
+    if !normals.is_empty() {
+        mesh.set_attribute(
+            Mesh::ATTRIBUTE_NORMAL,
+            VertexAttributeValues::from(
+                normals
+                    .iter()
+                    .map(|it| it.normalize_or_zero().to_array())
+                    .collect::<Vec<_>>(),
+            ),
+        );
+    }
+}
+
+/// Moves the vertices of skinned primitives along with their joints, see
+/// https://github.com/KhronosGroup/glTF-Tutorials/blob/master/gltfTutorial/gltfTutorial_020_Skins.md
+fn skin_vertices(
+    mut meshes: ResMut<Assets<Mesh>>,
+    mut skinned_query: Query<(
+        &GlobalTransform,
+        &Handle<Mesh>,
+        &mut SkinnedVertices,
+        Option<&mut Aabb>,
+    )>,
+    // The rows of the hierarchy carry node indices too
+    node_query: Query<(&NodeIndex, &GlobalTransform), Without<Node>>,
+) {
+    if skinned_query.is_empty() {
+        return;
+    }
+    let nodes: HashMap<NodeIndex, Mat4> = node_query
+        .iter()
+        .map(|(index, transform)| (*index, transform.compute_matrix()))
+        .collect();
+    for (transform, handle, mut vertices, aabb) in skinned_query.iter_mut() {
+        let to_mesh = transform.compute_matrix().inverse();
+        let joint_matrices: Vec<Mat4> = vertices
+            .joint_nodes
+            .iter()
+            .zip(&vertices.inverse_bind_matrices)
+            .map(|(joint, inverse_bind)| {
+                let joint = nodes.get(joint).copied().unwrap_or(Mat4::IDENTITY);
+                to_mesh * joint * *inverse_bind
+            })
+            .collect();
+        if joint_matrices == vertices.joint_matrices {
+            continue;
+        }
+        let skin_matrix = |index: usize| {
+            let (joints, weights) = (vertices.joints[index], vertices.weights[index]);
+            joints
+                .iter()
+                .zip(weights)
+                .fold(Mat4::ZERO, |matrix, (joint, weight)| {
+                    let joint = joint_matrices
+                        .get(*joint as usize)
+                        .copied()
+                        .unwrap_or(Mat4::IDENTITY);
+                    matrix + joint * weight
+                })
+        };
+        let matrices: Vec<Mat4> = (0..vertices.positions.len()).map(skin_matrix).collect();
+        let positions: Vec<Vec3> = matrices
+            .iter()
+            .zip(&vertices.positions)
+            .map(|(matrix, position)| matrix.transform_point3(*position))
+            .collect();
+        let normals: Vec<Vec3> = matrices
+            .iter()
+            .zip(&vertices.normals)
+            .map(|(matrix, normal)| matrix.transform_vector3(*normal))
+            .collect();
+        if let Some(mesh) = meshes.get_mut(handle) {
+            set_vertices(mesh, &positions, &normals);
+            // The bounds are used for culling and framing the camera
+            if let (Some(mut aabb), Some(bounds)) = (aabb, mesh.compute_aabb()) {
+                *aabb = bounds;
+            }
+        }
+        vertices.joint_matrices = joint_matrices;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+    use crate::hierarchy::MeshIndex;
+
+    /// A triangle skinned to node 0, with positions, joints and weights in one buffer
+    fn skinned_document() -> LoadedDocument {
+        let positions = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0];
+        let weights = [1.0f32, 0.0, 0.0, 0.0].repeat(3);
+        let bytes: Vec<u8> = positions
+            .iter()
+            .chain(&weights)
+            .flat_map(|value| value.to_le_bytes())
+            .chain([0u8; 12])
+            .collect();
+        LoadedDocument::from_json(&json!({
+            "asset": { "version": "2.0" },
+            "nodes": [{ "mesh": 0, "skin": 0 }],
+            "skins": [{ "joints": [0] }],
+            "meshes": [{
+                "primitives": [{
+                    "attributes": { "POSITION": 0, "WEIGHTS_0": 1, "JOINTS_0": 2 },
+                }],
+            }],
+            "buffers": [{
+                "byteLength": bytes.len(),
+                "uri": format!("data:application/octet-stream;base64,{}", base64::encode(&bytes)),
+            }],
+            "bufferViews": [
+                { "buffer": 0, "byteLength": 36 },
+                { "buffer": 0, "byteOffset": 36, "byteLength": 48 },
+                { "buffer": 0, "byteOffset": 84, "byteLength": 12 },
+            ],
+            "accessors": [
+                {
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": 3,
+                    "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0],
+                    "max": [1.0, 1.0, 0.0],
+                },
+                { "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC4" },
+                { "bufferView": 2, "componentType": 5121, "count": 3, "type": "VEC4" },
+            ],
+        }))
+    }
+
+    fn read_triangle(loaded: &LoadedDocument) -> Option<SkinnedVertices> {
+        let identifier = PrimitiveIdentifier {
+            mesh_index: MeshIndex(0),
+            primitive_index: 0,
+        };
+        let deformation = Deformation {
+            skin: Some(0),
+            weights: vec![],
+        };
+        read_vertices(
+            loaded.document.as_ref().unwrap(),
+            loaded,
+            &identifier,
+            &deformation,
+        )
+    }
+
+    #[test]
+    fn reads_skinned_vertices() {
+        let vertices = read_triangle(&skinned_document()).unwrap();
+        assert_eq!(vertices.positions[1], Vec3::X);
+        assert_eq!(vertices.joints, vec![[0; 4]; 3]);
+        assert_eq!(vertices.weights[2], [1.0, 0.0, 0.0, 0.0]);
+        assert_eq!(vertices.joint_nodes, vec![NodeIndex(0)]);
+        assert_eq!(vertices.inverse_bind_matrices, vec![Mat4::IDENTITY]);
+    }
+
+    #[test]
+    fn skips_accessors_past_their_buffers() {
+        let mut loaded = skinned_document();
+        // Cuts the joints off, whose reader would panic
+        loaded.buffers[0].0.truncate(84);
+        assert!(read_triangle(&loaded).is_none());
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::HashSet;
+
+use gltf::{mesh::Mode, Document};
+use serde::Serialize;
+
+use crate::{
+    document::LoadedDocument,
+    hierarchy::{MeshIndex, NodeIndex, PrimitiveIdentifier},
+    materials::texture_slots,
+    utils::format_bytes,
+};
+
+/// Totals of a set of drawn primitives. Meshes referenced by several nodes
+/// are drawn once per node, so triangles, vertices and draw calls count every
+/// instance, while the memory counts the data uploaded once per mesh.
+#[derive(Clone, Debug, Default, PartialEq, Serialize)]
+pub struct Statistics {
+    pub unique_meshes: usize,
+    pub mesh_instances: usize,
+    pub unique_primitives: usize,
+    /// One per drawn primitive
+    pub draw_calls: usize,
+    pub triangles: usize,
+    pub vertices: usize,
+    /// The default material is counted if a primitive has none
+    pub materials: usize,
+    pub textures: usize,
+    pub texture_pixels: usize,
+    /// Vertex and index data as stored in the file, and 4 bytes per texture pixel
+    pub gpu_memory: usize,
+}
+
+impl Statistics {
+    /// Computes the totals of the `primitives` drawn below their nodes
+    pub fn new(
+        gltf: &Document,
+        loaded: &LoadedDocument,
+        primitives: &[(NodeIndex, PrimitiveIdentifier)],
+    ) -> Self {
+        let meshes: Vec<gltf::Mesh> = gltf.meshes().collect();
+        let mut statistics = Statistics::default();
+        let mut unique_meshes = HashSet::new();
+        let mut instances = HashSet::new();
+        let mut unique_primitives = HashSet::new();
+        let mut materials = HashSet::new();
+        let mut geometry_memory = 0;
+        for (node, identifier) in primitives {
+            let primitive = match meshes
+                .get(identifier.mesh_index.0)
+                .and_then(|mesh| mesh.primitives().nth(identifier.primitive_index))
+            {
+                Some(it) => it,
+                None => continue,
+            };
+            unique_meshes.insert(identifier.mesh_index.0);
+            instances.insert((*node, identifier.mesh_index.0));
+            statistics.draw_calls += 1;
+            let vertices = primitive
+                .get(&gltf::Semantic::Positions)
+                .map(|positions| positions.count())
+                .unwrap_or_default();
+            statistics.vertices += vertices;
+            let elements = primitive
+                .indices()
+                .map(|indices| indices.count())
+                .unwrap_or(vertices);
+            statistics.triangles += match primitive.mode() {
+                Mode::Triangles => elements / 3,
+                Mode::TriangleStrip | Mode::TriangleFan => elements.saturating_sub(2),
+                _ => 0,
+            };
+            materials.insert(primitive.material().index());
+            if unique_primitives.insert((identifier.mesh_index.0, identifier.primitive_index)) {
+                geometry_memory += primitive
+                    .attributes()
+                    .map(|(_, accessor)| accessor)
+                    .chain(primitive.indices())
+                    .map(|accessor| accessor.count() * accessor.size())
+                    .sum::<usize>();
+            }
+        }
+
+        let textures: HashSet<usize> = gltf
+            .materials()
+            .filter(|material| materials.contains(&material.index()))
+            .flat_map(|material| texture_slots(&material))
+            .map(|slot| slot.texture)
+            .collect();
+        let images: HashSet<usize> = gltf
+            .textures()
+            .filter(|texture| textures.contains(&texture.index()))
+            .map(|texture| texture.source().index())
+            .collect();
+        statistics.texture_pixels = images
+            .iter()
+            .filter_map(|index| loaded.images.get(*index))
+            .map(|image| image.width as usize * image.height as usize)
+            .sum();
+
+        statistics.unique_meshes = unique_meshes.len();
+        statistics.mesh_instances = instances.len();
+        statistics.unique_primitives = unique_primitives.len();
+        statistics.materials = materials.len();
+        statistics.textures = textures.len();
+        statistics.gpu_memory = geometry_memory + statistics.texture_pixels * 4;
+        statistics
+    }
+
+    /// Computes the totals of all primitives drawn for a scene given its root nodes
+    pub fn of_scene<'a>(
+        roots: impl IntoIterator<Item = gltf::Node<'a>>,
+        gltf: &Document,
+        loaded: &LoadedDocument,
+    ) -> Self {
+        let mut primitives = vec![];
+        let mut nodes: Vec<gltf::Node> = roots.into_iter().collect();
+        while let Some(node) = nodes.pop() {
+            if let Some(mesh) = node.mesh() {
+                primitives.extend(mesh.primitives().map(|primitive| {
+                    (
+                        NodeIndex(node.index()),
+                        PrimitiveIdentifier {
+                            mesh_index: MeshIndex(mesh.index()),
+                            primitive_index: primitive.index(),
+                        },
+                    )
+                }));
+            }
+            nodes.extend(node.children());
+        }
+        Self::new(gltf, loaded, &primitives)
+    }
+
+    /// The totals as lines of text
+    pub fn to_text(&self) -> String {
+        format!(
+            "Meshes: {} unique, {} instances\n\
+             Primitives: {} unique, {} draw calls\n\
+             Triangles: {}\n\
+             Vertices: {}\n\
+             Materials: {}, textures: {}\n\
+             Texture pixels: {}\n\
+             GPU memory: ~{}",
+            self.unique_meshes,
+            self.mesh_instances,
+            self.unique_primitives,
+            self.draw_calls,
+            self.triangles,
+            self.vertices,
+            self.materials,
+            self.textures,
+            self.texture_pixels,
+            format_bytes(self.gpu_memory)
+        )
+    }
+}
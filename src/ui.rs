@@ -1,8 +1,16 @@
 use crate::{
-    utils::{get_current_scene, get_gltf},
+    document::{LoadErrors, LoadedDocument},
+    explosion::FallbackMaterial,
+    file_picker::FileChangedEvent,
+    hierarchy::{NodeIndex, PrimitiveIdentifier},
+    materials::texture_slots,
+    selection::Selection,
+    statistics::Statistics,
+    utils::{get_current_scene, get_gltf, primitive_bounds},
     InspectorState,
 };
 use bevy::prelude::*;
+use gltf::{texture, Document};
 
 pub struct UIPlugin;
 
@@ -10,9 +18,15 @@ impl Plugin for UIPlugin {
     fn build(&self, app: &mut App) {
         app.add_startup_system(explosion_text_setup)
             .add_system(explosion_text)
-            .add_system(scene_mesh_count)
+            .add_system(scene_statistics)
+            .add_system(selection_statistics)
+            .add_system(file_layout)
             .add_system(scene_info_name)
-            .add_system(scene_index);
+            .add_system(scene_index)
+            .add_system(primitive_details)
+            .add_system(material_warnings)
+            .add_system(load_errors)
+            .add_system(clear_status);
     }
 }
 
@@ -42,9 +56,6 @@ pub fn explosion_text_setup(mut commands: Commands, asset_server: Res<AssetServe
         });
 }
 
-#[derive(Component)]
-struct ScalingFactor;
-
 #[derive(Component)]
 struct InfoPanel;
 
@@ -85,9 +96,13 @@ fn spawn_left_panel(parent: &mut ChildBuilder, asset_server: &Res<AssetServer>)
                 value: "Scaling factor: \n".to_string(),
                 style: style.clone(),
             });
+            let statistics_style = TextStyle {
+                font_size: 16.0,
+                ..style.clone()
+            };
             text.sections.push(TextSection {
-                value: "MeshCount \n".to_string(),
-                style: style.clone(),
+                value: "Statistics\n".to_string(),
+                style: statistics_style.clone(),
             });
             text.sections.push(TextSection {
                 value: "_".repeat(32) + "\n",
@@ -97,6 +112,14 @@ fn spawn_left_panel(parent: &mut ChildBuilder, asset_server: &Res<AssetServer>)
                 value: "SceneIndex\n".to_string(),
                 style,
             });
+            text.sections.push(TextSection {
+                value: String::new(),
+                style: statistics_style.clone(),
+            });
+            text.sections.push(TextSection {
+                value: String::new(),
+                style: statistics_style,
+            });
             parent
                 .spawn_bundle(TextBundle {
                     text,
@@ -112,8 +135,63 @@ fn spawn_left_panel(parent: &mut ChildBuilder, asset_server: &Res<AssetServer>)
 #[derive(Component)]
 pub struct RightPanel;
 
+/// Reports the outcome of writing files, until another file is opened
+#[derive(Component)]
+pub struct StatusPanel;
+
+/// An empty section of the right panel, its children are laid out top to bottom
+pub fn right_section() -> NodeBundle {
+    NodeBundle {
+        style: Style {
+            flex_direction: FlexDirection::ColumnReverse,
+            margin: Rect {
+                top: Val::Px(6.0),
+                left: Val::Percent(2.0),
+                ..Default::default()
+            },
+            ..Default::default()
+        },
+        color: Color::NONE.into(),
+        ..Default::default()
+    }
+}
+
+/// An empty text of the right panel, whose lines are replaced as a whole
+pub fn text_panel(asset_server: &AssetServer, color: Color, top: Val) -> TextBundle {
+    TextBundle {
+        text: Text::with_section(
+            "",
+            TextStyle {
+                font: asset_server.load("fonts/FiraSans-Bold.ttf"),
+                font_size: 16.0,
+                color,
+            },
+            Default::default(),
+        ),
+        style: Style {
+            position: Rect {
+                top,
+                left: Val::Percent(2.0),
+                ..Default::default()
+            },
+            max_size: Size::new(Val::Px(280.), Val::Undefined),
+            ..Default::default()
+        },
+        ..Default::default()
+    }
+}
+
 #[derive(Component)]
 pub struct InstructionPanel;
+
+#[derive(Component)]
+struct DetailPanel;
+
+#[derive(Component)]
+struct WarningPanel;
+
+#[derive(Component)]
+struct ErrorPanel;
 fn spawn_right_panel(parent: &mut ChildBuilder, asset_server: &Res<AssetServer>) {
     parent
         .spawn_bundle(NodeBundle {
@@ -129,6 +207,13 @@ fn spawn_right_panel(parent: &mut ChildBuilder, asset_server: &Res<AssetServer>)
         })
         .insert(RightPanel)
         .with_children(|parent| {
+            parent
+                .spawn_bundle(text_panel(
+                    asset_server,
+                    Color::rgb(1.0, 0.3, 0.3),
+                    Val::Percent(1.0),
+                ))
+                .insert(ErrorPanel);
             let mut text = Text::with_section(
                 "How to use:\n",
                 TextStyle {
@@ -142,18 +227,46 @@ fn spawn_right_panel(parent: &mut ChildBuilder, asset_server: &Res<AssetServer>)
                 value: "
 Q+E: Increase and decrease explosion factor
 
+P: Play an explode and collapse cycle
+
+M: Switch between radial and linear explosion
+
 Left+Right Arrows: Switch between models
 
+Up+Down Arrows: Switch between scenes
+
 Scrolling: Zoom
 
-CTRL+Scrolling: Scroll through List
+CTRL+Scrolling: Scroll through the list under the cursor
 
 ScrollWheel pressed: Move Camera
 
 RightClick + Mouse Movement: Tilt Camera
 
+F: Frame the selection or the whole model
+
+1/3/7 (+Ctrl), 9: Front/Right/Top (Back/Left/Bottom), Iso view
+
+5 or O: Toggle orthographic view
+
+C: Cycle through the cameras of the file
+
+Space: Play or pause the animation, L: Loop
+
+V: Show or hide the validation issues
+
+T: Show or hide the materials and textures
+
 LeftClick: Select Mesh for inspection
 
+Click list entry: Select and focus Node
+
+Click +/-: Expand or collapse Node
+
+/: Filter the list, Enter selects matches
+
+H: Highlight the whole node of the selection
+
 Esc: Remove inspection view"
                     .to_string(),
                 style: TextStyle {
@@ -177,6 +290,15 @@ Esc: Remove inspection view"
                     ..Default::default()
                 })
                 .insert(InstructionPanel);
+            parent
+                .spawn_bundle(text_panel(asset_server, Color::WHITE, Val::Percent(3.0)))
+                .insert(DetailPanel);
+            parent
+                .spawn_bundle(text_panel(asset_server, Color::YELLOW, Val::Percent(3.0)))
+                .insert(WarningPanel);
+            parent
+                .spawn_bundle(text_panel(asset_server, Color::WHITE, Val::Percent(3.0)))
+                .insert(StatusPanel);
         });
 }
 
@@ -190,37 +312,254 @@ fn scene_info_name(state: Res<InspectorState>, mut query: Query<&mut Text, With<
 /// Show the current explosion factor
 fn explosion_text(state: Res<InspectorState>, mut query: Query<&mut Text, With<InfoPanel>>) {
     let mut text = query.single_mut();
-    text.sections[2].value = format!("Explosion factor: {:.1} \n", state.explosion_factor);
+    text.sections[2].value = format!(
+        "Explosion factor: {:.1} ({}) \n",
+        state.explosion_factor, state.explosion_mode
+    );
 }
 
-/// Show information about the current scene
-fn scene_mesh_count(state: Res<InspectorState>, mut query: Query<&mut Text, With<InfoPanel>>) {
-    if let Some(gltf) = get_gltf(&state) {
-        let scene = get_current_scene(&state, &gltf);
-        // Show information about the current scene
+/// Show the totals of the current scene
+fn scene_statistics(
+    state: Res<InspectorState>,
+    loaded: Res<LoadedDocument>,
+    mut query: Query<&mut Text, With<InfoPanel>>,
+) {
+    if !state.is_changed() && !loaded.is_changed() {
+        return;
+    }
+    if let Some(gltf) = get_gltf(&loaded) {
+        let roots = get_current_scene(&state, gltf);
+        let statistics = Statistics::of_scene(roots, gltf, &loaded);
         let mut text = query.single_mut();
-        text.sections[3].value =
-            format!("Mesh count is: {} \n", meshcount_for_nodes(scene.nodes()));
+        text.sections[3].value = format!("{}\n", statistics.to_text());
+    }
+}
+
+/// Show the totals of the selected primitives
+fn selection_statistics(
+    selection: Res<Selection>,
+    loaded: Res<LoadedDocument>,
+    primitive_query: Query<(&Parent, &PrimitiveIdentifier)>,
+    node_query: Query<&NodeIndex>,
+    mut query: Query<&mut Text, With<InfoPanel>>,
+) {
+    if !selection.is_changed() && !loaded.is_changed() {
+        return;
+    }
+    let mut text = query.single_mut();
+    let gltf = match get_gltf(&loaded) {
+        Some(it) if !selection.entities.is_empty() => it,
+        _ => {
+            text.sections[6].value.clear();
+            return;
+        }
+    };
+    let primitives: Vec<(NodeIndex, PrimitiveIdentifier)> = selection
+        .entities
+        .iter()
+        .filter_map(|entity| primitive_query.get(*entity).ok())
+        .filter_map(|(parent, identifier)| Some((*node_query.get(parent.0).ok()?, *identifier)))
+        .collect();
+    let statistics = Statistics::new(gltf, &loaded, &primitives);
+    text.sections[6].value = format!("Selection:\n{}\n", statistics.to_text());
+}
+
+/// Show the container of the current file and where its resources are stored
+fn file_layout(loaded: Res<LoadedDocument>, mut query: Query<&mut Text, With<InfoPanel>>) {
+    if !loaded.is_changed() {
+        return;
     }
+    query.single_mut().sections[7].value = loaded
+        .layout
+        .as_ref()
+        .map(|layout| format!("{}\n", layout.to_text()))
+        .unwrap_or_default();
 }
 
 /// Show information about the current scene
-fn scene_index(state: Res<InspectorState>, mut query: Query<&mut Text, With<InfoPanel>>) {
-    if let Some(gltf) = get_gltf(&state) {
-        let scene = get_current_scene(&state, &gltf);
+fn scene_index(
+    state: Res<InspectorState>,
+    loaded: Res<LoadedDocument>,
+    mut query: Query<&mut Text, With<InfoPanel>>,
+) {
+    if let Some(gltf) = get_gltf(&loaded) {
         // Show information about the current scene
         let mut text = query.single_mut();
-        text.sections[5].value = format!("Scene #{}: \n", scene.index());
+        text.sections[5].value = match state.current_scene {
+            Some(index) => format!("Scene #{} of {}: \n", index, gltf.scenes().count()),
+            None => "No scene, root nodes: \n".to_string(),
+        };
     }
 }
 
-fn meshcount_for_nodes<'a>(nodes: impl Iterator<Item = gltf::Node<'a>>) -> usize {
-    let mut count = 0;
-    for node in nodes {
-        if node.mesh().is_some() {
-            count += 1;
-        }
-        count += meshcount_for_nodes(node.children())
+/// Empties the status line when another file is opened, as it is about the previous file
+fn clear_status(
+    mut reader: EventReader<FileChangedEvent>,
+    mut query: Query<&mut Text, With<StatusPanel>>,
+) {
+    if reader.iter().next().is_some() {
+        query.single_mut().sections[0].value.clear();
+    }
+}
+
+/// Lists the files that could not be loaded and were skipped
+fn load_errors(load_errors: Res<LoadErrors>, mut query: Query<&mut Text, With<ErrorPanel>>) {
+    if !load_errors.is_changed() {
+        return;
+    }
+    let mut text = query.single_mut();
+    text.sections[0].value = load_errors
+        .errors
+        .iter()
+        .map(|error| {
+            // Invalid files can have thousands of validation errors
+            const MAX_LENGTH: usize = 500;
+            let mut message: String = error.message.chars().take(MAX_LENGTH).collect();
+            if message.len() < error.message.len() {
+                message.push_str("...");
+            }
+            format!("{}: {}\n{}\n", error.kind, error.path, message)
+        })
+        .collect();
+}
+
+/// Lists the drawn primitives without a material, which are drawn with the default one
+fn material_warnings(
+    loaded: Res<LoadedDocument>,
+    fallback_query: Query<&PrimitiveIdentifier, With<FallbackMaterial>>,
+    added_query: Query<(), Added<FallbackMaterial>>,
+    mut shown: Local<usize>,
+    mut query: Query<&mut Text, With<WarningPanel>>,
+) {
+    let count = fallback_query.iter().count();
+    if added_query.is_empty() && count == *shown {
+        return;
+    }
+    *shown = count;
+    let mut primitives: Vec<PrimitiveIdentifier> = fallback_query.iter().copied().collect();
+    primitives.sort_by_key(|it| (it.mesh_index.0, it.primitive_index));
+    primitives.dedup();
+    let lines: Vec<String> = primitives
+        .iter()
+        .map(|identifier| {
+            let name = get_gltf(&loaded)
+                .and_then(|gltf| gltf.meshes().nth(identifier.mesh_index.0))
+                .and_then(|mesh| mesh.name().map(|name| format!(" {}", name)))
+                .unwrap_or_default();
+            format!(
+                "Mesh {}{} primitive {}",
+                identifier.mesh_index.0, name, identifier.primitive_index
+            )
+        })
+        .collect();
+    let mut text = query.single_mut();
+    text.sections[0].value = if lines.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "\nWarning: {} primitives without material, drawn with the default material:\n{}",
+            lines.len(),
+            lines.join("\n")
+        )
+    };
+}
+
+/// Show the details of the clicked on primitive
+fn primitive_details(
+    selection: Res<Selection>,
+    loaded: Res<LoadedDocument>,
+    mut query: Query<&mut Text, With<DetailPanel>>,
+) {
+    if !selection.is_changed() && !loaded.is_changed() {
+        return;
+    }
+    let mut text = query.single_mut();
+    text.sections[0].value = selection
+        .primitive
+        .zip(get_gltf(&loaded))
+        .and_then(|(identifier, gltf)| describe_primitive(gltf, identifier))
+        .unwrap_or_default();
+}
+
+fn describe_primitive(gltf: &Document, identifier: PrimitiveIdentifier) -> Option<String> {
+    let mesh = gltf.meshes().nth(identifier.mesh_index.0)?;
+    let primitive = mesh.primitives().nth(identifier.primitive_index)?;
+
+    let mut lines = vec![
+        format!(
+            "Mesh {}: {}",
+            mesh.index(),
+            mesh.name().unwrap_or("unnamed")
+        ),
+        format!("Primitive {} ({:?})", primitive.index(), primitive.mode()),
+    ];
+    if let Some(positions) = primitive.get(&gltf::Semantic::Positions) {
+        lines.push(format!("Vertices: {}", positions.count()));
+    }
+    match primitive.indices() {
+        Some(indices) => lines.push(format!(
+            "Indices: {} ({:?})",
+            indices.count(),
+            indices.data_type()
+        )),
+        None => lines.push("Indices: none".to_string()),
+    }
+    lines.push("Attributes:".to_string());
+    for (semantic, accessor) in primitive.attributes() {
+        lines.push(format!(
+            "  {}: {:?} {:?}",
+            semantic.to_string(),
+            accessor.dimensions(),
+            accessor.data_type()
+        ));
+    }
+    if let Some(bounds) = primitive_bounds(&primitive) {
+        lines.push(format!(
+            "Bounds: {:.2?} to {:.2?}",
+            bounds.min.to_array(),
+            bounds.max.to_array()
+        ));
+    }
+
+    let material = primitive.material();
+    let pbr = material.pbr_metallic_roughness();
+    match material.index() {
+        Some(index) => lines.push(format!(
+            "Material {}: {}",
+            index,
+            material.name().unwrap_or("unnamed")
+        )),
+        None => lines.push("Material: default".to_string()),
+    }
+    lines.push(format!("  Base color: {:.2?}", pbr.base_color_factor()));
+    lines.push(format!(
+        "  Metallic: {:.2} Roughness: {:.2}",
+        pbr.metallic_factor(),
+        pbr.roughness_factor()
+    ));
+    lines.push(format!("  Emissive: {:.2?}", material.emissive_factor()));
+    let slots = texture_slots(&material);
+    if slots.is_empty() {
+        lines.push("  Textures: none".to_string());
+    }
+    for slot in slots {
+        lines.push(format!(
+            "  Texture {}: {}",
+            slot.slot,
+            texture_label(gltf.textures().nth(slot.texture))
+        ));
+    }
+
+    Some(lines.join("\n"))
+}
+
+fn texture_label(texture: Option<texture::Texture>) -> String {
+    match texture {
+        Some(texture) => format!(
+            "#{} {}",
+            texture.index(),
+            texture.source().name().unwrap_or("")
+        ),
+        None => "none".to_string(),
     }
-    count
 }
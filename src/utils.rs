@@ -1,21 +1,262 @@
+use std::collections::HashSet;
+
 use bevy::prelude::*;
-use gltf::Gltf;
+use gltf::Document;
 
-use crate::InspectorState;
+use crate::{document::LoadedDocument, InspectorState};
 
-/// Reads the gltf_file from disk should one be selected
-pub fn get_gltf(state: &Res<InspectorState>) -> Option<Gltf> {
-    state
-        .current_file
-        .and_then(|file| gltf::Gltf::open(format!("assets/{}", file.path)).ok())
+/// Returns the loaded document should a file be selected and readable
+pub fn get_gltf(loaded: &LoadedDocument) -> Option<&Document> {
+    loaded.document.as_ref()
 }
 
-/// Returns the currently active scene
+/// Returns the root nodes of the currently shown scene
 pub fn get_current_scene<'gltf>(
-    state: &Res<InspectorState>,
-    gltf: &'gltf Gltf,
-) -> gltf::Scene<'gltf> {
-    gltf.scenes()
-        .nth(state.current_file.unwrap().scene_index)
-        .unwrap()
+    state: &InspectorState,
+    gltf: &'gltf Document,
+) -> Vec<gltf::Node<'gltf>> {
+    scene_roots(gltf, state.current_scene)
+}
+
+/// Returns the root nodes of a scene, or the nodes without a parent should
+/// the scene not exist, as files without scenes only define a library of nodes
+pub fn scene_roots<'gltf>(
+    gltf: &'gltf Document,
+    scene_index: Option<usize>,
+) -> Vec<gltf::Node<'gltf>> {
+    if let Some(scene) = scene_index.and_then(|index| gltf.scenes().nth(index)) {
+        return scene.nodes().collect();
+    }
+    let children: HashSet<usize> = gltf
+        .nodes()
+        .flat_map(|node| node.children())
+        .map(|child| child.index())
+        .collect();
+    gltf.nodes()
+        .filter(|node| !children.contains(&node.index()))
+        .collect()
+}
+
+/// The scene shown when a file is opened: the default scene, or else the first one
+pub fn default_scene_index(gltf: &Document) -> Option<usize> {
+    gltf.default_scene()
+        .or_else(|| gltf.scenes().next())
+        .map(|scene| scene.index())
+}
+
+/// An axis aligned box in world space
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Bounds {
+    pub min: Vec3,
+    pub max: Vec3,
+}
+
+impl Bounds {
+    pub fn center(&self) -> Vec3 {
+        (self.min + self.max) * 0.5
+    }
+
+    /// Radius of the sphere enclosing the box
+    pub fn radius(&self) -> f32 {
+        (self.max - self.min).length() * 0.5
+    }
+
+    pub fn union(self, other: Bounds) -> Bounds {
+        Bounds {
+            min: self.min.min(other.min),
+            max: self.max.max(other.max),
+        }
+    }
+
+    /// Transforms all eight corners and returns the box enclosing them
+    pub fn transformed(&self, matrix: Mat4) -> Bounds {
+        let corners = (0..8).map(|i| {
+            Vec3::new(
+                if i & 1 == 0 { self.min.x } else { self.max.x },
+                if i & 2 == 0 { self.min.y } else { self.max.y },
+                if i & 4 == 0 { self.min.z } else { self.max.z },
+            )
+        });
+        let mut corners = corners.map(|corner| matrix.transform_point3(corner));
+        let first = corners.next().unwrap();
+        corners.fold(
+            Bounds {
+                min: first,
+                max: first,
+            },
+            |bounds, corner| Bounds {
+                min: bounds.min.min(corner),
+                max: bounds.max.max(corner),
+            },
+        )
+    }
+}
+
+/// Computes the world space bounds of a scene given its root nodes from the
+/// min/max values of the position accessors, so no buffer data has to be read
+pub fn scene_bounds<'a>(roots: impl IntoIterator<Item = gltf::Node<'a>>) -> Option<Bounds> {
+    roots
+        .into_iter()
+        .filter_map(|node| node_bounds(node, Mat4::IDENTITY))
+        .reduce(Bounds::union)
+}
+
+/// Bounds of a node and all its children, `parent` being the world transform of its parent
+pub fn node_bounds(node: gltf::Node, parent: Mat4) -> Option<Bounds> {
+    let world = parent * Mat4::from_cols_array_2d(&node.transform().matrix());
+    node.mesh()
+        .and_then(mesh_bounds)
+        .map(|bounds| bounds.transformed(world))
+        .into_iter()
+        .chain(
+            node.children()
+                .filter_map(|child| node_bounds(child, world)),
+        )
+        .reduce(Bounds::union)
+}
+
+/// Bounds of all primitives of a mesh in its local space
+pub fn mesh_bounds(mesh: gltf::Mesh) -> Option<Bounds> {
+    mesh.primitives()
+        .filter_map(|primitive| primitive_bounds(&primitive))
+        .reduce(Bounds::union)
+}
+
+/// Bounds of a primitive in its local space, read from its position accessor
+pub fn primitive_bounds(primitive: &gltf::Primitive) -> Option<Bounds> {
+    let accessor = primitive.get(&gltf::Semantic::Positions)?;
+    let min: [f32; 3] = gltf::json::deserialize::from_value(accessor.min()?).ok()?;
+    let max: [f32; 3] = gltf::json::deserialize::from_value(accessor.max()?).ok()?;
+    Some(Bounds {
+        min: Vec3::from(min),
+        max: Vec3::from(max),
+    })
+}
+
+/// Counts the distinct meshes referenced by `nodes` and their descendants,
+/// a mesh drawn by several nodes is counted once
+pub fn meshcount_for_nodes<'a>(nodes: impl Iterator<Item = gltf::Node<'a>>) -> usize {
+    let mut meshes = HashSet::new();
+    let mut nodes: Vec<gltf::Node> = nodes.collect();
+    while let Some(node) = nodes.pop() {
+        meshes.extend(node.mesh().map(|mesh| mesh.index()));
+        nodes.extend(node.children());
+    }
+    meshes.len()
+}
+
+/// Names of the morph targets of a mesh, which are stored in its extras by
+/// convention, falling back to their index
+pub fn morph_target_names(mesh: &gltf::Mesh) -> Vec<String> {
+    let count = mesh
+        .primitives()
+        .map(|primitive| primitive.morph_targets().count())
+        .max()
+        .unwrap_or_default();
+    let names: Vec<String> = mesh
+        .extras()
+        .as_ref()
+        .and_then(|extras| {
+            let extras: gltf::json::Value = gltf::json::deserialize::from_str(extras.get()).ok()?;
+            gltf::json::deserialize::from_value(extras.get("targetNames")?.clone()).ok()
+        })
+        .unwrap_or_default();
+    (0..count)
+        .map(|index| {
+            names
+                .get(index)
+                .cloned()
+                .unwrap_or_else(|| format!("Target {}", index))
+        })
+        .collect()
+}
+
+/// Case insensitive match of a name against a filter. Filters containing `*` or `?`
+/// are treated as glob patterns matching the whole name, all others as substrings.
+pub fn matches_filter(name: &str, filter: &str) -> bool {
+    let name: Vec<char> = name.to_lowercase().chars().collect();
+    let filter: Vec<char> = filter.to_lowercase().chars().collect();
+    if filter.contains(&'*') || filter.contains(&'?') {
+        glob_match(&name, &filter)
+    } else {
+        filter.is_empty() || name.windows(filter.len()).any(|window| window == filter)
+    }
+}
+
+/// Matches with backtracking only to the last `*`, which takes linear time per
+/// star instead of trying every split of the name for every star
+fn glob_match(name: &[char], pattern: &[char]) -> bool {
+    let (mut n, mut p) = (0, 0);
+    // The position after the last `*` and the name position it was matched up to
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p + 1, n));
+                p += 1;
+            }
+            Some(c) if *c == '?' || *c == name[n] => {
+                n += 1;
+                p += 1;
+            }
+            _ => match star {
+                // Let the last `*` match one more character and retry
+                Some((after_star, matched)) => {
+                    star = Some((after_star, matched + 1));
+                    p = after_star;
+                    n = matched + 1;
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|c| *c == '*')
+}
+
+/// Formats a size with a binary unit, e.g. `1.5 MiB`
+pub fn format_bytes(bytes: usize) -> String {
+    const UNITS: [&str; 4] = ["B", "KiB", "MiB", "GiB"];
+    let mut value = bytes as f64;
+    let mut unit = 0;
+    while value >= 1024.0 && unit < UNITS.len() - 1 {
+        value /= 1024.0;
+        unit += 1;
+    }
+    if unit == 0 {
+        format!("{} {}", bytes, UNITS[0])
+    } else {
+        format!("{:.1} {}", value, UNITS[unit])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn filters_without_wildcards_match_substrings() {
+        assert!(matches_filter("Wheel_Front_Left", "front"));
+        assert!(matches_filter("Wheel", ""));
+        assert!(!matches_filter("Wheel", "door"));
+    }
+
+    #[test]
+    fn wildcards_match_the_whole_name() {
+        assert!(matches_filter("Wheel_Front_Left", "wheel*"));
+        assert!(matches_filter("Wheel_Front_Left", "*left"));
+        assert!(matches_filter("Wheel_Front_Left", "w*f*t*"));
+        assert!(matches_filter("Door1", "door?"));
+        assert!(!matches_filter("Door12", "door?"));
+        assert!(!matches_filter("Wheel_Front_Left", "front*"));
+        assert!(matches_filter("", "*"));
+        assert!(!matches_filter("", "?"));
+    }
+
+    #[test]
+    fn many_stars_do_not_backtrack_exponentially() {
+        let name = "a".repeat(200);
+        let filter = "*a".repeat(30) + "b";
+        assert!(!matches_filter(&name, &filter));
+        assert!(matches_filter(&name, &"*a".repeat(30)));
+    }
 }
@@ -0,0 +1,762 @@
+use std::collections::HashSet;
+
+use bevy::prelude::*;
+use gltf::{accessor::DataType, Document};
+use serde::Serialize;
+
+use crate::{
+    document::{DocumentLoading, LoadedDocument},
+    file_picker::FileChangedEvent,
+    materials::texture_slots,
+    ui::{text_panel, RightPanel},
+};
+
+/// This plugin checks the opened file against the rules of the glTF
+/// specification that the loader doesn't enforce, and lists the issues found
+/// in a panel that is expanded with V
+pub struct ValidationPlugin;
+
+impl Plugin for ValidationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ValidationReport>()
+            .add_startup_system_to_stage(StartupStage::PostStartup, spawn_validation_panel)
+            .add_system(validate_document.after(DocumentLoading))
+            .add_system(toggle_validation_panel)
+            .add_system(update_validation_panel);
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Severity {
+    Error,
+    Warning,
+    Info,
+}
+
+/// A violation of the specification, or a likely mistake
+#[derive(Clone, Debug, Serialize)]
+pub struct Issue {
+    pub severity: Severity,
+    /// The JSON pointer to the offending object, e.g. `/meshes/0/primitives/1`
+    pub pointer: String,
+    pub message: String,
+}
+
+impl Issue {
+    fn new(severity: Severity, pointer: impl Into<String>, message: impl Into<String>) -> Self {
+        Self {
+            severity,
+            pointer: pointer.into(),
+            message: message.into(),
+        }
+    }
+}
+
+impl std::fmt::Display for Issue {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        if self.pointer.is_empty() {
+            write!(f, "{:?}: {}", self.severity, self.message)
+        } else {
+            write!(f, "{:?} {}: {}", self.severity, self.pointer, self.message)
+        }
+    }
+}
+
+/// The issues of the current file
+#[derive(Default)]
+pub struct ValidationReport {
+    pub issues: Vec<Issue>,
+    /// Whether the issues are listed in the panel, or only counted
+    pub expanded: bool,
+}
+
+pub fn count(issues: &[Issue], severity: Severity) -> usize {
+    issues
+        .iter()
+        .filter(|issue| issue.severity == severity)
+        .count()
+}
+
+/// Runs all checks on a loaded file, sorted by severity
+pub fn validate(loaded: &LoadedDocument) -> Vec<Issue> {
+    let gltf = match &loaded.document {
+        Some(it) => it,
+        None => return vec![],
+    };
+    let mut issues = vec![];
+    check_extensions(gltf, &mut issues);
+    issues.extend(hierarchy_issues(gltf));
+    check_data_uris(gltf, &mut issues);
+    check_buffer_views(gltf, loaded, &mut issues);
+    check_accessors(gltf, loaded, &mut issues);
+    check_primitives(gltf, loaded, &mut issues);
+    check_unused(gltf, &mut issues);
+    issues.sort_by_key(|issue| issue.severity);
+    issues
+}
+
+/// Converts the errors of a file the loader rejected into issues
+pub fn load_error_issues(err: &gltf::Error) -> Vec<Issue> {
+    match err {
+        gltf::Error::Validation(errors) => errors
+            .iter()
+            .map(|(path, error)| {
+                Issue::new(
+                    Severity::Error,
+                    json_pointer(&path.to_string()),
+                    error.to_string(),
+                )
+            })
+            .collect(),
+        _ => vec![Issue::new(Severity::Error, "", err.to_string())],
+    }
+}
+
+/// Converts a path like `meshes[0].primitives[1].attributes["POSITION"]` into a JSON pointer
+fn json_pointer(path: &str) -> String {
+    let mut pointer = String::new();
+    for segment in path.split(['.', '[']) {
+        let segment = segment.trim_end_matches(']').trim_matches('"');
+        if !segment.is_empty() {
+            pointer.push('/');
+            pointer.push_str(segment);
+        }
+    }
+    pointer
+}
+
+fn check_extensions(gltf: &Document, issues: &mut Vec<Issue>) {
+    let used: Vec<&str> = gltf.extensions_used().collect();
+    for (index, name) in gltf.extensions_required().enumerate() {
+        let pointer = format!("/extensionsRequired/{}", index);
+        if !used.contains(&name) {
+            issues.push(Issue::new(
+                Severity::Error,
+                &pointer,
+                format!("{} is required but missing from extensionsUsed", name),
+            ));
+        }
+        if !gltf::json::extensions::ENABLED_EXTENSIONS.contains(&name) {
+            issues.push(Issue::new(
+                Severity::Error,
+                &pointer,
+                format!("{} is required but not supported", name),
+            ));
+        }
+    }
+    for (index, name) in used.iter().enumerate() {
+        if !gltf::json::extensions::ENABLED_EXTENSIONS.contains(name) {
+            issues.push(Issue::new(
+                Severity::Warning,
+                format!("/extensionsUsed/{}", index),
+                format!("{} is not supported and will be ignored", name),
+            ));
+        }
+    }
+}
+
+/// Finds nodes with multiple parents and cycles in the node hierarchy, which
+/// would make the traversal of the scene run forever
+pub fn hierarchy_issues(gltf: &Document) -> Vec<Issue> {
+    let mut issues = vec![];
+    let mut parents = vec![0; gltf.nodes().count()];
+    for node in gltf.nodes() {
+        for child in node.children() {
+            parents[child.index()] += 1;
+            if parents[child.index()] == 2 {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    format!("/nodes/{}", child.index()),
+                    "Node has multiple parents",
+                ));
+            }
+        }
+    }
+    // Nodes are visited once, a node that is reached again while its
+    // descendants are visited is its own ancestor
+    let mut visited = vec![false; parents.len()];
+    let mut on_path = vec![false; parents.len()];
+    fn visit(
+        node: gltf::Node,
+        visited: &mut [bool],
+        on_path: &mut [bool],
+        issues: &mut Vec<Issue>,
+    ) {
+        visited[node.index()] = true;
+        on_path[node.index()] = true;
+        for (index, child) in node.children().enumerate() {
+            if on_path[child.index()] {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    format!("/nodes/{}/children/{}", node.index(), index),
+                    format!("Node {} is its own ancestor", child.index()),
+                ));
+            } else if !visited[child.index()] {
+                visit(child, visited, on_path, issues);
+            }
+        }
+        on_path[node.index()] = false;
+    }
+    for node in gltf.nodes() {
+        if !visited[node.index()] {
+            visit(node, &mut visited, &mut on_path, &mut issues);
+        }
+    }
+    for scene in gltf.scenes() {
+        for (index, node) in scene.nodes().enumerate() {
+            if parents[node.index()] > 0 {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    format!("/scenes/{}/nodes/{}", scene.index(), index),
+                    format!("Root node {} is the child of another node", node.index()),
+                ));
+            }
+        }
+    }
+    issues
+}
+
+/// The renderer only decodes data URI buffers with the MIME types of the specification
+fn check_data_uris(gltf: &Document, issues: &mut Vec<Issue>) {
+    for buffer in gltf.buffers() {
+        if let gltf::buffer::Source::Uri(uri) = buffer.source() {
+            let mime_type = uri
+                .strip_prefix("data:")
+                .and_then(|data| data.split([';', ',']).next());
+            if let Some(mime_type) = mime_type {
+                if !["application/octet-stream", "application/gltf-buffer"].contains(&mime_type) {
+                    issues.push(Issue::new(
+                        Severity::Warning,
+                        format!("/buffers/{}/uri", buffer.index()),
+                        format!("Data URI with MIME type '{}' will not be drawn", mime_type),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+fn check_buffer_views(gltf: &Document, loaded: &LoadedDocument, issues: &mut Vec<Issue>) {
+    for view in gltf.views() {
+        let pointer = format!("/bufferViews/{}", view.index());
+        let buffer = view.buffer();
+        let available = loaded
+            .buffers
+            .get(buffer.index())
+            .map(|data| data.len())
+            .unwrap_or_default()
+            .min(buffer.length());
+        if view.offset() + view.length() > available {
+            issues.push(Issue::new(
+                Severity::Error,
+                &pointer,
+                format!(
+                    "Bytes {}..{} exceed the {} bytes of buffer {}",
+                    view.offset(),
+                    view.offset() + view.length(),
+                    available,
+                    buffer.index()
+                ),
+            ));
+        }
+        if let Some(stride) = view.stride() {
+            if !(4..=252).contains(&stride) || stride % 4 != 0 {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    format!("{}/byteStride", pointer),
+                    format!("Stride {} is not a multiple of 4 between 4 and 252", stride),
+                ));
+            }
+        }
+    }
+}
+
+fn check_accessors(gltf: &Document, loaded: &LoadedDocument, issues: &mut Vec<Issue>) {
+    for accessor in gltf.accessors() {
+        let pointer = format!("/accessors/{}", accessor.index());
+        if let Some(view) = accessor.view() {
+            let stride = view.stride().unwrap_or_else(|| accessor.size());
+            let end = match accessor.count() {
+                0 => accessor.offset(),
+                count => accessor.offset() + stride * (count - 1) + accessor.size(),
+            };
+            if end > view.length() {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    &pointer,
+                    format!(
+                        "Elements end at byte {} of buffer view {} with {} bytes",
+                        end,
+                        view.index(),
+                        view.length()
+                    ),
+                ));
+                continue;
+            }
+            if accessor.offset() % accessor.data_type().size() != 0 {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    format!("{}/byteOffset", pointer),
+                    "Offset is not a multiple of the component size",
+                ));
+            }
+        }
+        check_min_max(&accessor, loaded, &pointer, issues);
+    }
+}
+
+/// Compares the declared bounds of an accessor with the bounds of its elements
+fn check_min_max(
+    accessor: &gltf::Accessor,
+    loaded: &LoadedDocument,
+    pointer: &str,
+    issues: &mut Vec<Issue>,
+) {
+    let declared = |value: Option<gltf::json::Value>| -> Option<Vec<f64>> {
+        gltf::json::deserialize::from_value(value?).ok()
+    };
+    let (min, max) = (declared(accessor.min()), declared(accessor.max()));
+    if min.is_none() || max.is_none() {
+        return;
+    }
+    // Sparse values would have to be substituted first
+    let elements = match accessor
+        .sparse()
+        .is_none()
+        .then(|| read_accessor(accessor, loaded))
+    {
+        Some(Some(it)) if !it.is_empty() => it,
+        _ => return,
+    };
+    let components = elements[0].len();
+    for (name, declared, actual) in [
+        (
+            "min",
+            min,
+            (0..components)
+                .map(|c| elements.iter().map(|e| e[c]).fold(f64::INFINITY, f64::min))
+                .collect::<Vec<_>>(),
+        ),
+        (
+            "max",
+            max,
+            (0..components)
+                .map(|c| {
+                    elements
+                        .iter()
+                        .map(|e| e[c])
+                        .fold(f64::NEG_INFINITY, f64::max)
+                })
+                .collect(),
+        ),
+    ] {
+        let declared = declared.unwrap_or_default();
+        let matches = declared.len() == actual.len()
+            && declared.iter().zip(&actual).all(|(declared, actual)| {
+                (declared - actual).abs() <= 1e-4 * declared.abs().max(1.0)
+            });
+        if !matches {
+            issues.push(Issue::new(
+                Severity::Error,
+                format!("{}/{}", pointer, name),
+                format!(
+                    "Declared {:?}, but the elements have {:?}",
+                    declared, actual
+                ),
+            ));
+        }
+    }
+}
+
+/// Reads the elements of a non-sparse accessor as floats, None if its data is out of range
+fn read_accessor(accessor: &gltf::Accessor, loaded: &LoadedDocument) -> Option<Vec<Vec<f64>>> {
+    let view = accessor.view()?;
+    let data = loaded.buffers.get(view.buffer().index())?;
+    let component_size = accessor.data_type().size();
+    let components = accessor.dimensions().multiplicity();
+    let stride = view.stride().unwrap_or_else(|| accessor.size());
+    let start = view.offset() + accessor.offset();
+    (0..accessor.count())
+        .map(|element| {
+            (0..components)
+                .map(|component| {
+                    let offset = start + element * stride + component * component_size;
+                    let bytes = data.get(offset..offset + component_size)?;
+                    Some(match accessor.data_type() {
+                        DataType::I8 => bytes[0] as i8 as f64,
+                        DataType::U8 => bytes[0] as f64,
+                        DataType::I16 => i16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                        DataType::U16 => u16::from_le_bytes([bytes[0], bytes[1]]) as f64,
+                        DataType::U32 => {
+                            u32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                        }
+                        DataType::F32 => {
+                            f32::from_le_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]) as f64
+                        }
+                    })
+                })
+                .collect()
+        })
+        .collect()
+}
+
+fn check_primitives(gltf: &Document, loaded: &LoadedDocument, issues: &mut Vec<Issue>) {
+    for mesh in gltf.meshes() {
+        for primitive in mesh.primitives() {
+            let pointer = format!("/meshes/{}/primitives/{}", mesh.index(), primitive.index());
+            let positions = match primitive.get(&gltf::Semantic::Positions) {
+                Some(it) => it,
+                None => {
+                    issues.push(Issue::new(
+                        Severity::Warning,
+                        format!("{}/attributes", pointer),
+                        "Primitive has no POSITION attribute",
+                    ));
+                    continue;
+                }
+            };
+            if positions.min().is_none() || positions.max().is_none() {
+                issues.push(Issue::new(
+                    Severity::Error,
+                    format!("/accessors/{}", positions.index()),
+                    "POSITION accessors must define min and max",
+                ));
+            }
+            let vertex_count = positions.count();
+            for (semantic, accessor) in primitive.attributes() {
+                if accessor.count() != vertex_count {
+                    issues.push(Issue::new(
+                        Severity::Error,
+                        format!("{}/attributes/{}", pointer, semantic.to_string()),
+                        format!(
+                            "{} elements, but POSITION has {}",
+                            accessor.count(),
+                            vertex_count
+                        ),
+                    ));
+                }
+            }
+            // Accessors outside of their buffers were reported by check_accessors
+            // and check_buffer_views, reading them would panic
+            let readable = |accessor: Option<gltf::Accessor>| {
+                accessor.is_some_and(|accessor| loaded.accessor_in_bounds(&accessor))
+            };
+            let reader =
+                primitive.reader(|buffer| loaded.buffers.get(buffer.index()).map(|data| &data[..]));
+            if let Some(indices) = readable(primitive.indices())
+                .then(|| reader.read_indices())
+                .flatten()
+            {
+                let out_of_range = indices
+                    .into_u32()
+                    .filter(|index| *index as usize >= vertex_count)
+                    .count();
+                if out_of_range > 0 {
+                    issues.push(Issue::new(
+                        Severity::Error,
+                        format!("{}/indices", pointer),
+                        format!(
+                            "{} indices are not below the vertex count {}",
+                            out_of_range, vertex_count
+                        ),
+                    ));
+                }
+            }
+            if let Some(normals) = readable(primitive.get(&gltf::Semantic::Normals))
+                .then(|| reader.read_normals())
+                .flatten()
+            {
+                let invalid = normals
+                    .filter(|normal| (Vec3::from(*normal).length() - 1.0).abs() > 0.01)
+                    .count();
+                if invalid > 0 {
+                    issues.push(Issue::new(
+                        Severity::Warning,
+                        format!("{}/attributes/NORMAL", pointer),
+                        format!(
+                            "{} of {} normals are not unit length",
+                            invalid, vertex_count
+                        ),
+                    ));
+                }
+            }
+            if let Some(tangents) = readable(primitive.get(&gltf::Semantic::Tangents))
+                .then(|| reader.read_tangents())
+                .flatten()
+            {
+                let invalid = tangents
+                    .filter(|[x, y, z, w]| {
+                        (Vec3::new(*x, *y, *z).length() - 1.0).abs() > 0.01
+                            || (w.abs() - 1.0).abs() > f32::EPSILON
+                    })
+                    .count();
+                if invalid > 0 {
+                    issues.push(Issue::new(
+                        Severity::Warning,
+                        format!("{}/attributes/TANGENT", pointer),
+                        format!(
+                            "{} of {} tangents are not unit length or have a w other than 1 or -1",
+                            invalid, vertex_count
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+}
+
+/// Lists the objects no other object refers to
+fn check_unused(gltf: &Document, issues: &mut Vec<Issue>) {
+    let mut used_nodes = HashSet::new();
+    let mut stack: Vec<gltf::Node> = gltf.scenes().flat_map(|scene| scene.nodes()).collect();
+    while let Some(node) = stack.pop() {
+        if used_nodes.insert(node.index()) {
+            stack.extend(node.children());
+        }
+    }
+    let nodes = || {
+        gltf.nodes()
+            .filter(|node| used_nodes.contains(&node.index()))
+    };
+    let used_meshes: HashSet<usize> = nodes()
+        .filter_map(|node| Some(node.mesh()?.index()))
+        .collect();
+    let used_skins: HashSet<usize> = nodes()
+        .filter_map(|node| Some(node.skin()?.index()))
+        .collect();
+    let used_cameras: HashSet<usize> = nodes()
+        .filter_map(|node| Some(node.camera()?.index()))
+        .collect();
+
+    let mut used_accessors = HashSet::new();
+    let mut used_materials = HashSet::new();
+    for primitive in gltf.meshes().flat_map(|mesh| mesh.primitives()) {
+        used_accessors.extend(primitive.attributes().map(|(_, accessor)| accessor.index()));
+        used_accessors.extend(primitive.indices().map(|accessor| accessor.index()));
+        for target in primitive.morph_targets() {
+            used_accessors.extend(
+                [target.positions(), target.normals(), target.tangents()]
+                    .into_iter()
+                    .flatten()
+                    .map(|accessor| accessor.index()),
+            );
+        }
+        used_materials.extend(primitive.material().index());
+    }
+    for skin in gltf.skins() {
+        used_accessors.extend(
+            skin.inverse_bind_matrices()
+                .map(|accessor| accessor.index()),
+        );
+    }
+    for sampler in gltf.animations().flat_map(|animation| animation.samplers()) {
+        used_accessors.insert(sampler.input().index());
+        used_accessors.insert(sampler.output().index());
+    }
+
+    let mut used_textures = HashSet::new();
+    for material in gltf.materials() {
+        used_textures.extend(texture_slots(&material).iter().map(|slot| slot.texture));
+    }
+    let used_images: HashSet<usize> = gltf
+        .textures()
+        .map(|texture| texture.source().index())
+        .collect();
+    let used_samplers: HashSet<usize> = gltf
+        .textures()
+        .filter_map(|texture| texture.sampler().index())
+        .collect();
+
+    let mut used_views = HashSet::new();
+    for accessor in gltf.accessors() {
+        used_views.extend(accessor.view().map(|view| view.index()));
+        if let Some(sparse) = accessor.sparse() {
+            used_views.insert(sparse.indices().view().index());
+            used_views.insert(sparse.values().view().index());
+        }
+    }
+    for image in gltf.images() {
+        if let gltf::image::Source::View { view, .. } = image.source() {
+            used_views.insert(view.index());
+        }
+    }
+    let used_buffers: HashSet<usize> = gltf.views().map(|view| view.buffer().index()).collect();
+
+    let mut report_unused = |kind: &str, count: usize, used: &HashSet<usize>| {
+        for index in (0..count).filter(|index| !used.contains(index)) {
+            issues.push(Issue::new(
+                Severity::Info,
+                format!("/{}/{}", kind, index),
+                "Object is not used",
+            ));
+        }
+    };
+    report_unused("nodes", gltf.nodes().count(), &used_nodes);
+    report_unused("meshes", gltf.meshes().count(), &used_meshes);
+    report_unused("skins", gltf.skins().count(), &used_skins);
+    report_unused("cameras", gltf.cameras().count(), &used_cameras);
+    report_unused("accessors", gltf.accessors().count(), &used_accessors);
+    report_unused("materials", gltf.materials().count(), &used_materials);
+    report_unused("textures", gltf.textures().count(), &used_textures);
+    report_unused("images", gltf.images().count(), &used_images);
+    report_unused("samplers", gltf.samplers().count(), &used_samplers);
+    report_unused("bufferViews", gltf.views().count(), &used_views);
+    report_unused("buffers", gltf.buffers().count(), &used_buffers);
+}
+
+#[derive(Component)]
+struct ValidationPanel;
+
+fn spawn_validation_panel(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    right_panel_query: Query<Entity, With<RightPanel>>,
+) {
+    let panel = commands
+        .spawn_bundle(text_panel(
+            &asset_server,
+            Color::rgb(1.0, 0.8, 0.5),
+            Val::Percent(3.0),
+        ))
+        .insert(ValidationPanel)
+        .id();
+    commands.entity(right_panel_query.single()).add_child(panel);
+}
+
+fn validate_document(
+    mut reader: EventReader<FileChangedEvent>,
+    loaded: Res<LoadedDocument>,
+    mut report: ResMut<ValidationReport>,
+) {
+    if reader.iter().next().is_none() {
+        return;
+    }
+    report.issues = validate(&loaded);
+    for issue in report
+        .issues
+        .iter()
+        .filter(|issue| issue.severity == Severity::Error)
+    {
+        warn!("{}", issue);
+    }
+}
+
+fn toggle_validation_panel(
+    keyboard_input: Res<Input<KeyCode>>,
+    mut report: ResMut<ValidationReport>,
+) {
+    if keyboard_input.is_changed() && keyboard_input.just_pressed(KeyCode::V) {
+        report.expanded = !report.expanded;
+    }
+}
+
+fn update_validation_panel(
+    report: Res<ValidationReport>,
+    mut query: Query<&mut Text, With<ValidationPanel>>,
+) {
+    if !report.is_changed() {
+        return;
+    }
+    let mut lines = vec![format!(
+        "\nValidation: {} errors, {} warnings, {} infos",
+        count(&report.issues, Severity::Error),
+        count(&report.issues, Severity::Warning),
+        count(&report.issues, Severity::Info),
+    )];
+    if report.expanded {
+        const SHOWN_ISSUES: usize = 20;
+        lines.extend(
+            report
+                .issues
+                .iter()
+                .take(SHOWN_ISSUES)
+                .map(|issue| issue.to_string()),
+        );
+        if report.issues.len() > SHOWN_ISSUES {
+            lines.push(format!("... {} more", report.issues.len() - SHOWN_ISSUES));
+        }
+    }
+    query.single_mut().sections[0].value = lines.join("\n");
+}
+
+#[cfg(test)]
+mod tests {
+    use serde_json::json;
+
+    use super::*;
+
+    fn document(json: serde_json::Value) -> LoadedDocument {
+        LoadedDocument::from_json(&json)
+    }
+
+    fn pointers(issues: &[Issue]) -> Vec<&str> {
+        issues.iter().map(|issue| issue.pointer.as_str()).collect()
+    }
+
+    #[test]
+    fn accepts_a_tree() {
+        let loaded = document(json!({
+            "asset": { "version": "2.0" },
+            "nodes": [{ "children": [1, 2] }, {}, { "children": [3] }, {}],
+            "scenes": [{ "nodes": [0] }],
+        }));
+        assert!(hierarchy_issues(loaded.document.as_ref().unwrap()).is_empty());
+    }
+
+    #[test]
+    fn finds_cycles_multiple_parents_and_child_roots() {
+        let loaded = document(json!({
+            "asset": { "version": "2.0" },
+            "nodes": [{ "children": [1, 2] }, { "children": [0] }, {}, { "children": [2] }],
+            "scenes": [{ "nodes": [0, 3] }],
+        }));
+        let issues = hierarchy_issues(loaded.document.as_ref().unwrap());
+        assert!(issues.iter().all(|issue| issue.severity == Severity::Error));
+        assert_eq!(
+            pointers(&issues),
+            ["/nodes/2", "/nodes/1/children/0", "/scenes/0/nodes/0"]
+        );
+    }
+
+    #[test]
+    fn reports_accessors_past_their_views_without_reading_them() {
+        let positions: Vec<u8> = [0.0f32, 0.0, 0.0, 1.0, 0.0, 0.0, 0.0, 1.0, 0.0]
+            .iter()
+            .flat_map(|value| value.to_le_bytes())
+            .collect();
+        let loaded = document(json!({
+            "asset": { "version": "2.0" },
+            "buffers": [{
+                "byteLength": positions.len(),
+                "uri": format!("data:application/octet-stream;base64,{}", base64::encode(&positions)),
+            }],
+            "bufferViews": [
+                { "buffer": 0, "byteLength": 36 },
+                { "buffer": 0, "byteLength": 6 },
+            ],
+            "accessors": [
+                {
+                    "bufferView": 0,
+                    "componentType": 5126,
+                    "count": 3,
+                    "type": "VEC3",
+                    "min": [0.0, 0.0, 0.0],
+                    "max": [1.0, 1.0, 0.0],
+                },
+                { "bufferView": 1, "componentType": 5123, "count": 30, "type": "SCALAR" },
+                { "bufferView": 1, "componentType": 5126, "count": 3, "type": "VEC3" },
+            ],
+            "meshes": [{
+                "primitives": [{ "attributes": { "POSITION": 0, "NORMAL": 2 }, "indices": 1 }],
+            }],
+            "nodes": [{ "mesh": 0 }],
+            "scenes": [{ "nodes": [0] }],
+        }));
+        let issues = validate(&loaded);
+        let pointers = pointers(&issues);
+        assert!(pointers.contains(&"/accessors/1"));
+        assert!(pointers.contains(&"/accessors/2"));
+        assert!(!pointers.contains(&"/meshes/0/primitives/0/indices"));
+    }
+}